@@ -5,7 +5,9 @@ use chrono::Duration;
 use gloo::timers::future::sleep;
 use indexmap::{indexmap, IndexMap};
 use sycamore::{generic_node::GenericNodeElements, web::html};
-use ubongo_extreme_solver::{canonicalize_place, Axial, Game, Solver};
+use ubongo_extreme_solver::{
+    canonicalize_place, Axial, Budget, Game, Hex, Lattice, RunOutcome, RunResult, Solution, Solver, SolverOptions, Triangle, TriangleCell,
+};
 
 use crate::prelude::*;
 
@@ -85,18 +87,49 @@ fn unit_hexagon_path() -> String {
     s
 }
 
+// the corners of the up triangle, its center is at the origin
+const UNIT_TRIANGLE_UP_PATH: &str = "M -0.8660254037844386 -0.5 L 0.8660254037844386 -0.5 L 0 1 Z";
+const UNIT_TRIANGLE_DOWN_PATH: &str = "M 0 -1 L 0.8660254037844386 0.5 L -0.8660254037844386 0.5 Z";
+
+const SQRT_3: f64 = 1.7320508075688772;
+
 fn axial_to_cartesian(Axial(x, y): Axial) -> DVec2 {
-    const SQRT_3: f64 = 1.7320508075688772;
     let x = x as f64;
     let y = y as f64;
     dvec2(SQRT_3 * x + (SQRT_3 / 2.0) * y, (3.0 / 2.0) * y)
 }
 
-fn hexagon(pos: DVec2, color: &'static str) -> View {
+/// How the cells of a lattice are drawn.
+trait Render: Lattice {
+    /// Returns the id of the template of the cell and the position of its center.
+    fn cell(coord: Axial) -> (&'static str, DVec2);
+}
+
+impl Render for Hex {
+    fn cell(coord: Axial) -> (&'static str, DVec2) {
+        ("#hexagon", axial_to_cartesian(coord))
+    }
+}
+
+impl Render for Triangle {
+    /// The corners of the triangles are the centers of the hexagons.
+    fn cell(coord: Axial) -> (&'static str, DVec2) {
+        let TriangleCell { corner, up } = coord.into();
+        let corner = axial_to_cartesian(corner);
+
+        if up {
+            ("#triangle-up", corner + dvec2(SQRT_3 / 2.0, 0.5))
+        } else {
+            ("#triangle-down", corner + dvec2(SQRT_3, 1.0))
+        }
+    }
+}
+
+fn cell_view((href, pos): (&'static str, DVec2), color: &'static str) -> View {
     use sycamore::builder::prelude::*;
 
     r#use()
-        .attr("href", "#hexagon")
+        .attr("href", href)
         .attr("fill", color)
         .attr("x", pos.x.to_string())
         .attr("y", pos.y.to_string())
@@ -107,11 +140,11 @@ fn hexagon(pos: DVec2, color: &'static str) -> View {
     // }
 }
 
-fn hexagon_dyn(pos: DVec2, color: Signal<&'static str>) -> View {
+fn cell_view_dyn((href, pos): (&'static str, DVec2), color: Signal<&'static str>) -> View {
     use sycamore::builder::prelude::*;
 
     r#use()
-        .attr("href", "#hexagon")
+        .attr("href", href)
         .dyn_attr("fill", move || Some(color.get()))
         .attr("x", pos.x.to_string())
         .attr("y", pos.y.to_string())
@@ -134,8 +167,71 @@ pub struct SolverTracedSignals {
     working_piece_fill_color: Signal<&'static str>,
 }
 
+/// The parts of a [`Solver`] the app uses, whatever its lattice.
+trait TracedSolver {
+    fn board(&self) -> &[Axial];
+    fn solutions_len(&self) -> usize;
+    fn solution(&self, index: usize) -> &Solution;
+    /// Returns the cells of the pieces that are currently placed, in the order they were placed in.
+    fn placed_pieces(&self) -> Vec<(usize, &[Axial])>;
+    fn run(&mut self, budget: &Budget) -> RunResult;
+}
+
+impl<L: Lattice> TracedSolver for Solver<L> {
+    fn board(&self) -> &[Axial] {
+        &self.game.board
+    }
+
+    fn solutions_len(&self) -> usize {
+        self.solutions.len()
+    }
+
+    fn solution(&self, index: usize) -> &Solution {
+        &self.solutions[index]
+    }
+
+    fn placed_pieces(&self) -> Vec<(usize, &[Axial])> {
+        self.placed().map(|piece| (piece, self.pieces[piece].as_slice())).collect()
+    }
+
+    fn run(&mut self, budget: &Budget) -> RunResult {
+        Solver::run(self, budget)
+    }
+}
+
+/// A game on one of the lattices the app can draw.
+///
+/// The JSON format is the one of [`Game`], with an optional `lattice` field that is either `"hex"` or `"triangle"`.
+#[derive(Debug, Clone)]
+pub enum AnyGame {
+    Hex(Game<Hex>),
+    Triangle(Game<Triangle>),
+}
+
+impl Default for AnyGame {
+    fn default() -> Self {
+        Self::Hex(default())
+    }
+}
+
+impl AnyGame {
+    pub fn from_json5(s: &str) -> Result<Self, String> {
+        let value: serde_json::Value = json5::from_str(s).map_err(|error| error.to_string())?;
+
+        let game = match value.get("lattice").and_then(|lattice| lattice.as_str()).unwrap_or("hex") {
+            "hex" => Self::Hex(json5::from_str(s).map_err(|error| error.to_string())?),
+            "triangle" => Self::Triangle(json5::from_str(s).map_err(|error| error.to_string())?),
+            lattice => return Err(format!("unknown lattice {lattice:?}")),
+        };
+
+        Ok(game)
+    }
+}
+
 pub struct SolverTraced {
-    solver: Solver,
+    solver: Box<dyn TracedSolver>,
+    /// Where and how a cell of the lattice of the solver is drawn.
+    cell: fn(Axial) -> (&'static str, DVec2),
     steps: usize,
     is_done: bool,
     duration: chrono::Duration,
@@ -149,7 +245,8 @@ pub struct SolverTraced {
 impl SolverTraced {
     pub fn new(signals: SolverTracedSignals) -> Self {
         Self {
-            solver: Solver::new(default()),
+            solver: Box::new(Solver::<Hex>::new(default())),
+            cell: Hex::cell,
             duration: Duration::zero(),
             boards_colors_signals: Vec::new(),
             board_color_buffer: default(),
@@ -161,9 +258,16 @@ impl SolverTraced {
         }
     }
 
-    pub fn set_game(&mut self, mut game: Game) {
+    pub fn set_game(&mut self, game: AnyGame) {
+        match game {
+            AnyGame::Hex(game) => self.set_lattice_game(game),
+            AnyGame::Triangle(game) => self.set_lattice_game(game),
+        }
+    }
+
+    fn set_lattice_game<L: Render>(&mut self, mut game: Game<L>) {
         canonicalize_place(&mut game.board);
-        let mut aabb = Aabb::from_points_axial(game.board.iter().copied());
+        let mut aabb = Aabb::from_points(game.board.iter().map(|&coord| L::cell(coord).1));
         aabb = aabb.expand(PADDING);
 
         self.viewbox = aabb.viewbox();
@@ -178,7 +282,7 @@ impl SolverTraced {
 
         for piece in &game.pieces {
             for &coord in piece {
-                let point = axial_to_cartesian(coord);
+                let (_, point) = L::cell(coord);
                 pieces_aabb = pieces_aabb.expand_to(point);
             }
         }
@@ -186,26 +290,33 @@ impl SolverTraced {
         let pieces_aabb_size = pieces_aabb.expand(PADDING).size();
 
         for (piece_i, piece) in game.pieces.iter().enumerate() {
-            let mut hexagons = vec![];
+            let mut cells = vec![];
             let mut aabb = Aabb::EMPTY;
 
             for &coord in piece {
-                let point = axial_to_cartesian(coord);
-                aabb = aabb.expand_to(point);
+                let cell = L::cell(coord);
+                aabb = aabb.expand_to(cell.1);
                 let color = COLORS[piece_i % COLORS.len()];
-                hexagons.push(hexagon(point, color));
+                cells.push(cell_view(cell, color));
             }
 
             let aabb = Aabb::from_origin_size(aabb.origin(), pieces_aabb_size);
 
             use sycamore::builder::prelude::*;
 
-            let view = div().c(svg().attr("viewBox", aabb.viewbox()).c(View::new_fragment(hexagons))).view();
+            let view = div().c(svg().attr("viewBox", aabb.viewbox()).c(View::new_fragment(cells))).view();
 
             pieces.push(view);
         }
 
-        self.solver = Solver::new(game);
+        self.solver = Box::new(Solver::with_options(
+            game,
+            SolverOptions {
+                reorder_pieces: true,
+                ..default()
+            },
+        ));
+        self.cell = L::cell;
         self.duration = Duration::zero();
         self.boards_colors_signals = Vec::new();
         self.steps = 0;
@@ -237,7 +348,7 @@ impl SolverTraced {
         }
     }
 
-    fn make_board(&self, hexagons: Vec<View>) -> View {
+    fn make_board(&self, cells: Vec<View>) -> View {
         use sycamore::builder::prelude::*;
 
         let size = self.signals.item_size;
@@ -248,17 +359,17 @@ impl SolverTraced {
 
         div()
             .dyn_attr("style", style)
-            .c(svg().attr("viewBox", self.viewbox.clone()).c(View::new_fragment(hexagons)))
+            .c(svg().attr("viewBox", self.viewbox.clone()).c(View::new_fragment(cells)))
             .view()
     }
 
     fn something_changed(&mut self) {
         self.signals.duration.set(self.duration);
         self.signals.steps.set(self.steps);
-        self.signals.solutions_len.set(self.solver.solutions.len());
+        self.signals.solutions_len.set(self.solver.solutions_len());
         self.signals.is_done.set(self.is_done);
 
-        let mut board_count = self.solver.solutions.len();
+        let mut board_count = self.solver.solutions_len();
 
         if !self.is_done {
             self.steps += 1;
@@ -266,17 +377,17 @@ impl SolverTraced {
         }
 
         while board_count > self.boards_colors_signals.len() {
-            let mut hexagons = vec![];
+            let mut cells = vec![];
             let mut colors = Vec::new();
 
-            for &coord in &self.solver.game.board {
+            for &coord in self.solver.board() {
                 let signal = create_signal(COLOR_DEFAULT);
-                hexagons.push(hexagon_dyn(axial_to_cartesian(coord), signal));
+                cells.push(cell_view_dyn((self.cell)(coord), signal));
                 colors.push(signal);
             }
 
             self.boards_colors_signals.push(colors);
-            self.signals.boards_views.update(|v| v.push(self.make_board(hexagons)));
+            self.signals.boards_views.update(|v| v.push(self.make_board(cells)));
         }
 
         while board_count < self.boards_colors_signals.len() {
@@ -284,10 +395,10 @@ impl SolverTraced {
             self.signals.boards_views.update(|v| v.pop());
         }
 
-        while self.solutions < self.solver.solutions.len() {
+        while self.solutions < self.solver.solutions_len() {
             self.clear_board_color_buffer();
 
-            for (piece_i, piece) in self.solver.solutions[self.solutions].iter().enumerate() {
+            for (piece_i, piece) in self.solver.solution(self.solutions).iter().enumerate() {
                 for coord in piece {
                     if let Some(color) = self.board_color_buffer.get_mut(&coord.key()) {
                         *color = COLORS[piece_i % COLORS.len()];
@@ -302,8 +413,10 @@ impl SolverTraced {
         if !self.is_done {
             self.clear_board_color_buffer();
 
-            for (piece_i, piece) in self.solver.pieces.iter().enumerate().take(self.solver.work_idx) {
-                let is_last = piece_i == self.solver.work_idx - 1;
+            let placed = self.solver.placed_pieces();
+
+            for (i, &(piece_i, piece)) in placed.iter().enumerate() {
+                let is_last = i == placed.len() - 1;
 
                 for coord in piece {
                     if let Some(color) = self.board_color_buffer.get_mut(&coord.key()) {
@@ -329,13 +442,22 @@ impl SolverTraced {
     }
 
     pub fn step_silent(&mut self) {
-        let start = chrono::Local::now();
-        self.is_done = self.solver.next().is_none();
-        let now = chrono::Local::now();
-        self.duration += now - start;
+        let result = self.solver.run(&Budget {
+            max_steps: Some(1),
+            clock: chrono_clock,
+            ..default()
+        });
+
+        self.is_done = result.outcome == RunOutcome::Finished;
+        self.duration += Duration::from_std(result.elapsed).unwrap_or_else(|_| Duration::zero());
     }
 }
 
+/// `Instant` is not available in the browser.
+fn chrono_clock() -> std::time::Duration {
+    (chrono::Utc::now() - chrono::DateTime::UNIX_EPOCH).to_std().unwrap_or_default()
+}
+
 const COLORS: &[&str] = &[
     "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f", "#bcbd22", "#17becf",
 ];
@@ -344,7 +466,7 @@ const PADDING: f64 = 1.5;
 
 #[component]
 pub fn App() -> View {
-    let examples: Signal<IndexMap<&'static str, &'static str>> = create_signal(examples!["b18b", "b18g", "b18r", "b18y", "b38y", "b4"]);
+    let examples: Signal<IndexMap<&'static str, &'static str>> = create_signal(examples!["b18b", "b18g", "b18r", "b18y", "b38y", "b4", "t24"]);
 
     let examples_keys = create_memo(move || examples.with(|v| v.keys().copied().collect::<Vec<_>>()));
     let example_key = create_signal(String::from("b38y"));
@@ -356,9 +478,9 @@ pub fn App() -> View {
     create_effect(move || game_json.set(example_value.get_clone()));
 
     let view = create_signal(false);
-    let game = create_signal(Game::default());
+    let game = create_signal(AnyGame::default());
 
-    create_effect(move || match game_json.with(|v| json5::from_str::<Game>(v)) {
+    create_effect(move || match game_json.with(|v| AnyGame::from_json5(v)) {
         Ok(g) => game.set(g),
         Err(error) => error!(%error),
     });
@@ -461,6 +583,8 @@ pub fn App() -> View {
         svg(id="templates", viewBox="-10 -10 20 20") {
             defs {
                 path(id="hexagon", d=unit_hexagon_path())
+                path(id="triangle-up", d=UNIT_TRIANGLE_UP_PATH)
+                path(id="triangle-down", d=UNIT_TRIANGLE_DOWN_PATH)
                 pattern(id="working-piece-fill", height="2", width="2", patternUnits="userSpaceOnUse", patternTransform="translate(1,0) rotate(60) scale(0.17)") {
                     rect(fill=COLOR_DEFAULT, width="2", height="2")
                     rect(fill=*working_piece_fill_color.get(), width="1", height="2")
@@ -646,11 +770,6 @@ impl Aabb {
         this
     }
 
-    #[must_use]
-    pub fn from_points_axial(points: impl IntoIterator<Item = Axial>) -> Self {
-        Self::from_points(points.into_iter().map(axial_to_cartesian))
-    }
-
     #[must_use]
     pub fn expand(mut self, value: f64) -> Self {
         self.min -= value;
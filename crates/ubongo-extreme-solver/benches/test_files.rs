@@ -27,7 +27,26 @@ macro_rules! files {
 	};
 }
 
+macro_rules! dlx_files {
+	($($name:ident $file:ident)*) => {
+		$(
+			fn $name(c: &mut Criterion) {
+				let game = test_file(stringify!($file));
+
+				c.bench_function(stringify!($name), |b| {
+					b.iter(|| {
+						let mut solver = game.clone().dlx_solver();
+						solver.solve();
+						solver
+					})
+				});
+			}
+		)*
+	};
+}
+
 files!(b4 b38y);
+dlx_files!(dlx_b4 b4 dlx_b38y b38y);
 
-criterion_group!(benches, b4, b38y);
+criterion_group!(benches, b4, b38y, dlx_b4, dlx_b38y);
 criterion_main!(benches);
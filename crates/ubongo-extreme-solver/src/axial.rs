@@ -5,7 +5,7 @@ use std::{
     ops::{Add, AddAssign, Sub, SubAssign},
 };
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Axial(pub i32, pub i32);
 
 #[allow(clippy::derived_hash_with_manual_eq)]
@@ -55,6 +55,7 @@ impl Axial {
     pub const ZERO: Axial = Axial::splat(0);
     pub const MIN: Axial = Axial::splat(i32::MIN);
     pub const MAX: Axial = Axial::splat(i32::MAX);
+    pub const DIRECTIONS: [Axial; 6] = [Axial(1, 0), Axial(1, -1), Axial(0, -1), Axial(-1, 0), Axial(-1, 1), Axial(0, 1)];
 
     #[must_use]
     pub const fn splat(i: i32) -> Self {
@@ -76,6 +77,10 @@ impl Axial {
         Cube::from(self).rotate_many(pivot.into(), steps).into()
     }
 
+    pub fn neighbours(self) -> impl Iterator<Item = Self> {
+        Self::DIRECTIONS.into_iter().map(move |direction| self + direction)
+    }
+
     #[must_use]
     pub fn min(self, other: Self) -> Self {
         Self(self.0.min(other.0), self.1.min(other.1))
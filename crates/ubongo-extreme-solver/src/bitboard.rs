@@ -0,0 +1,361 @@
+use crate::{aabb, Axial, Lattice};
+use serde::{Deserialize, Serialize};
+use smallvec::{smallvec, SmallVec};
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Shl, Sub, SubAssign},
+};
+
+/// How many words are stored without allocating, enough for boards with a bounding box of 256 cells.
+const INLINE_WORDS: usize = 4;
+
+/// A set of cell indices, stored as words of bits that grow as needed.
+///
+/// Cells are mapped to indices by a [`BoardLayout`].
+/// Missing words count as zero, so sets of different lengths compare equal if they have the same bits.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Bitboard(SmallVec<[u64; INLINE_WORDS]>);
+
+impl Bitboard {
+    pub const ZERO: Self = Self(SmallVec::new_const());
+
+    #[must_use]
+    pub fn bit(index: usize) -> Self {
+        let mut words = smallvec![0; index / 64 + 1];
+        words[index / 64] = 1 << (index % 64);
+        Self(words)
+    }
+
+    pub fn set(&mut self, index: usize) {
+        if self.0.len() <= index / 64 {
+            self.0.resize(index / 64 + 1, 0);
+        }
+
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    #[must_use]
+    pub fn contains(&self, index: usize) -> bool {
+        self.0.get(index / 64).is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    #[must_use]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0.iter().enumerate().all(|(i, &word)| word & !other.0.get(i).copied().unwrap_or(0) == 0)
+    }
+
+    /// Returns whether the sets have no index in common.
+    #[must_use]
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.0.iter().zip(&other.0).all(|(&a, &b)| a & b == 0)
+    }
+
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Iterates over the indices of all set bits in ascending order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+
+    /// Returns the words without the trailing zero words.
+    fn words(&self) -> &[u64] {
+        let len = self.0.iter().rposition(|&word| word != 0).map_or(0, |last| last + 1);
+        &self.0[..len]
+    }
+}
+
+impl PartialEq for Bitboard {
+    fn eq(&self, other: &Self) -> bool {
+        self.words() == other.words()
+    }
+}
+
+impl Eq for Bitboard {}
+
+impl Hash for Bitboard {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.words().hash(state);
+    }
+}
+
+impl PartialOrd for Bitboard {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Bitboard {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.words().cmp(other.words())
+    }
+}
+
+impl Shl<usize> for &Bitboard {
+    type Output = Bitboard;
+
+    fn shl(self, amount: usize) -> Bitboard {
+        let words = self.words();
+        let word_shift = amount / 64;
+        let bit_shift = amount % 64;
+
+        let Some(&last) = words.last() else {
+            return Bitboard::ZERO;
+        };
+
+        // only grow by a word if the last one carries bits into it, so boards that fit inline stay inline
+        let carry = bit_shift != 0 && last >> (64 - bit_shift) != 0;
+        let mut shifted = smallvec![0; words.len() + word_shift + usize::from(carry)];
+
+        for (src, &word) in words.iter().enumerate() {
+            shifted[src + word_shift] |= word << bit_shift;
+
+            if bit_shift != 0 && src + word_shift + 1 < shifted.len() {
+                shifted[src + word_shift + 1] |= word >> (64 - bit_shift);
+            }
+        }
+
+        Bitboard(shifted)
+    }
+}
+
+impl Shl<usize> for Bitboard {
+    type Output = Self;
+
+    fn shl(self, amount: usize) -> Self {
+        &self << amount
+    }
+}
+
+macro_rules! bitwise {
+    ($($trait:ident $fn:ident $assign_trait:ident $assign_fn:ident $op:tt $union:literal)*) => {
+        $(
+            impl $assign_trait<&Bitboard> for Bitboard {
+                fn $assign_fn(&mut self, other: &Self) {
+                    // words missing in either set are zero
+                    if $union && self.0.len() < other.0.len() {
+                        self.0.resize(other.0.len(), 0);
+                    }
+
+                    for (a, b) in self.0.iter_mut().zip(&other.0) {
+                        *a $op b;
+                    }
+
+                    if !$union {
+                        self.0.truncate(other.0.len());
+                    }
+                }
+            }
+
+            impl $assign_trait for Bitboard {
+                fn $assign_fn(&mut self, other: Self) {
+                    *self $op &other;
+                }
+            }
+
+            impl $trait<&Bitboard> for Bitboard {
+                type Output = Self;
+
+                fn $fn(mut self, other: &Self) -> Self {
+                    self $op other;
+                    self
+                }
+            }
+
+            impl $trait for Bitboard {
+                type Output = Self;
+
+                fn $fn(mut self, other: Self) -> Self {
+                    self $op &other;
+                    self
+                }
+            }
+
+            impl $trait for &Bitboard {
+                type Output = Bitboard;
+
+                fn $fn(self, other: Self) -> Bitboard {
+                    let mut result = self.clone();
+                    result $op other;
+                    result
+                }
+            }
+        )*
+    };
+}
+
+bitwise! {
+    BitAnd bitand BitAndAssign bitand_assign &= false
+    BitOr bitor BitOrAssign bitor_assign |= true
+    BitXor bitxor BitXorAssign bitxor_assign ^= true
+}
+
+/// The difference of two sets, the indices of the first that are not in the second.
+impl SubAssign<&Bitboard> for Bitboard {
+    fn sub_assign(&mut self, other: &Self) {
+        for (a, b) in self.0.iter_mut().zip(&other.0) {
+            *a &= !b;
+        }
+    }
+}
+
+impl SubAssign for Bitboard {
+    fn sub_assign(&mut self, other: Self) {
+        *self -= &other;
+    }
+}
+
+impl Sub<&Bitboard> for Bitboard {
+    type Output = Self;
+
+    fn sub(mut self, other: &Self) -> Self {
+        self -= other;
+        self
+    }
+}
+
+impl Sub for Bitboard {
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self {
+        self -= &other;
+        self
+    }
+}
+
+impl Sub for &Bitboard {
+    type Output = Bitboard;
+
+    fn sub(self, other: Self) -> Bitboard {
+        let mut result = self.clone();
+        result -= other;
+        result
+    }
+}
+
+/// Maps the cells inside the bounding box of a board to bit indices, row by row.
+///
+/// Because the mapping is linear, translating a piece inside the bounding box is a left shift of its mask.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BoardLayout {
+    pub min: Axial,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl BoardLayout {
+    pub fn new(board: &[Axial]) -> Self {
+        if board.is_empty() {
+            return Self::default();
+        }
+
+        let aabb = aabb(board);
+        let size = aabb.size();
+        Self {
+            min: aabb.min,
+            width: size.0 + 1,
+            height: size.1 + 1,
+        }
+    }
+
+    pub fn len(self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    pub fn size(self) -> Axial {
+        Axial(self.width, self.height)
+    }
+
+    #[must_use]
+    pub fn index(self, coord: Axial) -> Option<usize> {
+        let Axial(x, y) = coord - self.min;
+
+        if (0..self.width).contains(&x) && (0..self.height).contains(&y) {
+            Some((x + y * self.width) as usize)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub fn coord(self, index: usize) -> Axial {
+        let index = index as i32;
+        Axial(index % self.width, index / self.width) + self.min
+    }
+
+    /// Returns `None` if any of the coordinates lies outside of the layout.
+    #[must_use]
+    pub fn mask(self, coords: &[Axial]) -> Option<Bitboard> {
+        let mut mask = Bitboard::ZERO;
+
+        for &coord in coords {
+            mask.set(self.index(coord)?);
+        }
+
+        Some(mask)
+    }
+
+    pub fn cells(self, mask: &Bitboard) -> impl Iterator<Item = Axial> + '_ {
+        mask.ones().map(move |index| self.coord(index))
+    }
+
+    /// Returns the mask of the neighbouring cells on the lattice for every index.
+    pub fn neighbours<L: Lattice>(self) -> Vec<Bitboard> {
+        (0..self.len())
+            .map(|index| {
+                let mut mask = Bitboard::ZERO;
+
+                for neighbour in L::neighbours(self.coord(index)) {
+                    if let Some(index) = self.index(neighbour) {
+                        mask.set(index);
+                    }
+                }
+
+                mask
+            })
+            .collect()
+    }
+
+    /// Splits the mask into its connected regions.
+    pub fn regions(self, neighbours: &[Bitboard], mut mask: Bitboard) -> impl Iterator<Item = Bitboard> + '_ {
+        std::iter::from_fn(move || {
+            let seed = mask.ones().next()?;
+            let mut region = Bitboard::bit(seed);
+            let mut frontier = region.clone();
+
+            while !frontier.is_zero() {
+                let mut grown = Bitboard::ZERO;
+
+                for index in frontier.ones() {
+                    grown |= &neighbours[index];
+                }
+
+                frontier = (grown & &mask) - &region;
+                region |= &frontier;
+            }
+
+            mask -= &region;
+            Some(region)
+        })
+    }
+}
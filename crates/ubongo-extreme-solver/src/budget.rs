@@ -0,0 +1,111 @@
+use crate::{Lattice, SearchEvent, Solver};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// A flag that stops a search running on another thread at its next step.
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Limits how much work [`Solver::run`] may do.
+#[derive(Debug, Clone)]
+pub struct Budget {
+    pub max_steps: Option<usize>,
+    /// The wall-clock time the run may take, measured with `clock`.
+    pub time_limit: Option<Duration>,
+    pub cancel: Option<CancelToken>,
+    /// Returns the time elapsed since an arbitrary but fixed moment.
+    ///
+    /// The default is based on [`Instant`], which is not available on `wasm32-unknown-unknown`.
+    pub clock: fn() -> Duration,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            time_limit: None,
+            cancel: None,
+            clock: instant_clock,
+        }
+    }
+}
+
+fn instant_clock() -> Duration {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed()
+}
+
+/// Why [`Solver::run`] stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// All solutions have been found.
+    Finished,
+    Cancelled,
+    StepLimit,
+    TimeLimit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunResult {
+    pub outcome: RunOutcome,
+    pub steps: usize,
+    pub elapsed: Duration,
+}
+
+impl<L: Lattice> Solver<L> {
+    /// Searches until all solutions have been found or the budget is used up.
+    ///
+    /// The search can be resumed by calling this again, or by stepping the iterator.
+    pub fn run(&mut self, budget: &Budget) -> RunResult {
+        let start = (budget.clock)();
+        let mut steps = 0;
+
+        let outcome = loop {
+            if budget.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+                break RunOutcome::Cancelled;
+            }
+
+            if budget.max_steps.is_some_and(|max_steps| steps >= max_steps) {
+                break RunOutcome::StepLimit;
+            }
+
+            if budget.time_limit.is_some_and(|time_limit| (budget.clock)().saturating_sub(start) >= time_limit) {
+                break RunOutcome::TimeLimit;
+            }
+
+            let Some(event) = self.next() else {
+                break RunOutcome::Finished;
+            };
+
+            steps += 1;
+
+            if event == SearchEvent::Finished {
+                break RunOutcome::Finished;
+            }
+        };
+
+        let elapsed = (budget.clock)().saturating_sub(start);
+        self.stats.elapsed += elapsed;
+
+        RunResult { outcome, steps, elapsed }
+    }
+}
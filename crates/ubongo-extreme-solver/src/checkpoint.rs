@@ -0,0 +1,163 @@
+use crate::{
+    solution_bytes, Axial, Bitboard, Choice, Choices, Game, GameError, Hex, Lattice, PieceCopy, Placer, SearchEvent, Solution, Solver, SolverOptions,
+    SolverStats,
+};
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fmt};
+
+/// The state of a search as plain data, so it can be saved and resumed later.
+///
+/// Everything that can be derived from the game, the options and the permutations is recomputed by [`Solver::resume`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "")]
+pub struct Checkpoint<L: Lattice = Hex> {
+    /// The game with the copies of its pieces listed separately.
+    pub game: Game<L>,
+    pub copies: Vec<PieceCopy>,
+    pub options: SolverOptions,
+    /// The permutations the placements were built from, which are not always those of the game, see [`LayeredSolver`](crate::LayeredSolver).
+    pub pieces_permutations: Vec<Vec<Vec<Axial>>>,
+    /// The choices left at every depth, including the cursor into the placements of the piece.
+    pub placers: Vec<Placer>,
+    pub pieces: Vec<Vec<Axial>>,
+    pub masks: Vec<Bitboard>,
+    pub is_placed: Vec<bool>,
+    pub stabilizer: Vec<usize>,
+    pub work_idx: usize,
+    pub finished: bool,
+    /// Events that have not been yielded by the iterator yet.
+    pub events: VecDeque<SearchEvent>,
+    pub solutions: IndexSet<Solution>,
+    pub coverage: Vec<Vec<usize>>,
+    pub stats: SolverStats,
+}
+
+/// Why a [`Checkpoint`] can't be resumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// The game of the checkpoint is invalid.
+    InvalidGame(GameError),
+    /// `field` does not match the pieces of the game, or `work_idx` lies beyond the placers.
+    WrongLength { field: &'static str },
+    /// The placer at `depth` refers to a piece or placement that does not exist.
+    InvalidPlacer { depth: usize },
+    /// The stabilizer refers to a symmetry that does not exist.
+    InvalidSymmetry { symmetry: usize },
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidGame(error) => write!(f, "invalid game: {error}"),
+            Self::WrongLength { field } => write!(f, "{field} does not match the game"),
+            Self::InvalidPlacer { depth } => write!(f, "the placer at depth {depth} refers to a placement that does not exist"),
+            Self::InvalidSymmetry { symmetry } => write!(f, "symmetry {symmetry} does not exist"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<GameError> for CheckpointError {
+    fn from(error: GameError) -> Self {
+        Self::InvalidGame(error)
+    }
+}
+
+impl<L: Lattice> Solver<L> {
+    pub fn checkpoint(&self) -> Checkpoint<L> {
+        Checkpoint {
+            game: self.game.clone(),
+            copies: self.copies.clone(),
+            options: self.options,
+            pieces_permutations: self.pieces_permutations.clone(),
+            placers: self.placers.clone(),
+            pieces: self.pieces.clone(),
+            masks: self.masks.clone(),
+            is_placed: self.is_placed.clone(),
+            stabilizer: self.stabilizer.clone(),
+            work_idx: self.work_idx,
+            finished: self.finished,
+            events: self.events.clone(),
+            solutions: self.solutions.clone(),
+            coverage: self.coverage.clone(),
+            stats: self.stats.clone(),
+        }
+    }
+
+    /// Continues a search where [`Self::checkpoint`] left it.
+    ///
+    /// Fails if the checkpoint does not fit its game, so a stored checkpoint can't make the search panic.
+    pub fn resume(checkpoint: Checkpoint<L>) -> Result<Self, CheckpointError> {
+        let count = checkpoint.game.pieces.len();
+        let lengths = [
+            ("copies", checkpoint.copies.len()),
+            ("pieces_permutations", checkpoint.pieces_permutations.len()),
+            ("pieces", checkpoint.pieces.len()),
+            ("masks", checkpoint.masks.len()),
+            ("is_placed", checkpoint.is_placed.len()),
+        ];
+
+        if let Some(&(field, _)) = lengths.iter().find(|&&(_, len)| len != count) {
+            return Err(CheckpointError::WrongLength { field });
+        }
+
+        if checkpoint.work_idx > checkpoint.placers.len() {
+            return Err(CheckpointError::WrongLength { field: "placers" });
+        }
+
+        let permutations = checkpoint.pieces_permutations;
+        let mut this = Self::try_with_permutations(checkpoint.game, checkpoint.options, |_| permutations)?;
+
+        if let Some(depth) = checkpoint.placers.iter().position(|placer| !this.is_valid_placer(placer)) {
+            return Err(CheckpointError::InvalidPlacer { depth });
+        }
+
+        if let Some(&symmetry) = checkpoint.stabilizer.iter().find(|&&symmetry| symmetry >= this.symmetry_maps.len()) {
+            return Err(CheckpointError::InvalidSymmetry { symmetry });
+        }
+
+        let coverage_len = |coverage: &[Vec<usize>]| coverage.iter().map(Vec::len).collect::<Vec<_>>();
+
+        if coverage_len(&checkpoint.coverage) != coverage_len(&this.coverage) {
+            return Err(CheckpointError::WrongLength { field: "coverage" });
+        }
+
+        this.copies = checkpoint.copies;
+        this.placers = checkpoint.placers;
+        this.pieces = checkpoint.pieces;
+        this.masks = checkpoint.masks;
+        this.is_placed = checkpoint.is_placed;
+        this.stabilizer = checkpoint.stabilizer;
+        this.work_idx = checkpoint.work_idx;
+        this.finished = checkpoint.finished;
+        this.events = checkpoint.events;
+        this.solutions = checkpoint.solutions;
+        this.solutions_bytes = this.solutions.iter().map(solution_bytes).sum();
+        this.coverage = checkpoint.coverage;
+        this.stats = checkpoint.stats;
+
+        Ok(this)
+    }
+
+    /// Returns whether every piece and placement the placer refers to exists.
+    fn is_valid_placer(&self, placer: &Placer) -> bool {
+        let is_valid_choice = |choice: &Choice| match *choice {
+            Choice::Place { piece, placement, ref mask } => self
+                .placements
+                .get(piece)
+                .and_then(|placements| placements.get(placement))
+                .is_some_and(|placement| placement.mask == *mask),
+            Choice::LeaveEmpty { ref cell } => cell.count() == 1,
+            Choice::Skip { piece } => piece < self.game.pieces.len(),
+        };
+
+        let is_valid_choices = match placer.choices {
+            Choices::Piece { piece, cursor, .. } => self.placements.get(piece).is_some_and(|placements| cursor <= placements.len()),
+            Choices::List(ref choices) => choices.iter().all(is_valid_choice),
+        };
+
+        is_valid_choices && placer.placed.is_none_or(|piece| piece < self.game.pieces.len())
+    }
+}
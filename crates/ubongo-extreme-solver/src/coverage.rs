@@ -0,0 +1,193 @@
+use crate::{
+    canonicalize_place, group_identical, place, placement, Axial, Bitboard, BoardLayout, Game, GameError, Hex, Lattice, PieceCopy, Placement, Solution,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// The best arrangement found by a [`CoverageSolver`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Arrangement {
+    /// The cells of every piece, pieces that are left out have none.
+    pub pieces: Solution,
+    /// The total weight of the covered cells.
+    pub score: u64,
+    pub uncovered: Vec<Axial>,
+}
+
+/// Places the pieces without overlap so that the covered cells weigh as much as possible, see [`Game::weights`].
+///
+/// Any piece may be left out, so there always is an arrangement, even if the board can't be tiled.
+/// The pieces of [`Game::fixed`] stay where they are, [`Game::piece_count`] is ignored.
+#[derive(Debug, Clone)]
+pub struct CoverageSolver<L: Lattice = Hex> {
+    /// The game with the copies of its pieces listed separately, see [`Game::expand_copies`].
+    pub game: Game<L>,
+    pub copies: Vec<PieceCopy>,
+    pub best: Arrangement,
+    pub steps: usize,
+    layout: BoardLayout,
+    /// The cells left free by the fixed pieces.
+    board: Bitboard,
+    /// The cells of the fixed pieces, the other pieces have none.
+    fixed: Vec<Vec<Axial>>,
+    placements: Vec<Vec<Placement>>,
+    /// The weight of each placement of each piece.
+    placement_weights: Vec<Vec<u64>>,
+    /// The pieces from largest to smallest, identical pieces stay in order.
+    order: Vec<usize>,
+    previous_identical: Vec<Option<usize>>,
+    /// The board cells with their weights, heaviest first.
+    by_weight: Vec<(usize, u64)>,
+    total: u64,
+    chosen: Vec<Option<usize>>,
+}
+
+impl<L: Lattice> CoverageSolver<L> {
+    /// Panics if the game is invalid, see [`Game::validate`].
+    pub fn new(game: Game<L>) -> Self {
+        Self::try_new(game).unwrap_or_else(|error| panic!("invalid game: {error}"))
+    }
+
+    pub fn try_new(game: Game<L>) -> Result<Self, GameError> {
+        game.validate()?;
+
+        let (game, copies) = game.expand_copies();
+        let layout = BoardLayout::new(&game.board);
+        let mut board = layout.mask(&game.board).expect("board fits into its own layout");
+        let mut fixed = vec![Vec::new(); game.pieces.len()];
+
+        for placement in &game.fixed {
+            place(&mut board, &layout.mask(&placement.cells).expect("fixed pieces are on the board"));
+            fixed[placement.piece] = placement.cells.clone();
+            canonicalize_place(&mut fixed[placement.piece]);
+        }
+
+        let is_fixed: Vec<bool> = fixed.iter().map(|cells| !cells.is_empty()).collect();
+        let mut pieces_permutations = game.pieces_permutations();
+        let (_, previous_identical) = group_identical(&mut pieces_permutations, &is_fixed);
+        let placements = placement::placement_table::<L>(&game.board, &pieces_permutations);
+
+        let mut weights = vec![0; layout.len()];
+        let mut by_weight = Vec::new();
+
+        for (i, &cell) in game.board.iter().enumerate() {
+            let index = layout.index(cell).expect("board fits into its own layout");
+            weights[index] = game.weight(i) as u64;
+            by_weight.push((index, weights[index]));
+        }
+
+        by_weight.sort_by_key(|&(_, weight)| Reverse(weight));
+
+        let placement_weights = placements
+            .iter()
+            .map(|placements| {
+                placements
+                    .iter()
+                    .map(|placement| placement.mask.ones().map(|index| weights[index]).sum())
+                    .collect()
+            })
+            .collect();
+
+        // fixed pieces are never placed by the search
+        let mut order: Vec<usize> = (0..game.pieces.len()).filter(|&piece| !is_fixed[piece]).collect();
+        order.sort_by_key(|&piece| Reverse(game.pieces[piece].len()));
+
+        Ok(Self {
+            best: Arrangement {
+                pieces: fixed.clone(),
+                score: fixed
+                    .iter()
+                    .flatten()
+                    .map(|&cell| weights[layout.index(cell).expect("fixed pieces are on the board")])
+                    .sum(),
+                uncovered: layout.cells(&board).collect(),
+            },
+            steps: 0,
+            layout,
+            board,
+            fixed,
+            placements,
+            placement_weights,
+            order,
+            previous_identical,
+            total: by_weight.iter().map(|&(_, weight)| weight).sum(),
+            by_weight,
+            chosen: vec![None; game.pieces.len()],
+            copies,
+            game,
+        })
+    }
+
+    /// The most the pieces of the given area could add by covering the heaviest free cells.
+    fn bound(&self, board: &Bitboard, area: usize) -> u64 {
+        self.by_weight
+            .iter()
+            .filter(|&&(index, _)| board.contains(index))
+            .take(area)
+            .map(|&(_, weight)| weight)
+            .sum()
+    }
+
+    fn record_best(&mut self, board: &Bitboard, score: u64) {
+        let pieces = self
+            .chosen
+            .iter()
+            .enumerate()
+            .map(|(piece, chosen)| match *chosen {
+                Some(placement) => self.placements[piece][placement].cells.clone(),
+                None => self.fixed[piece].clone(),
+            })
+            .collect();
+
+        self.best = Arrangement {
+            pieces,
+            score,
+            uncovered: self.layout.cells(board).collect(),
+        };
+    }
+
+    fn search(&mut self, depth: usize, board: Bitboard, score: u64, area: usize) {
+        self.steps += 1;
+
+        if score > self.best.score {
+            self.record_best(&board, score);
+        }
+
+        if depth == self.order.len() || self.best.score == self.total || score + self.bound(&board, area) <= self.best.score {
+            return;
+        }
+
+        let piece = self.order[depth];
+        let area = area - self.game.pieces[piece].len();
+
+        // identical pieces are placed in order, and only if the previous one is
+        let first = match self.previous_identical[piece] {
+            Some(previous) => self.chosen[previous].map(|placement| placement + 1),
+            None => Some(0),
+        };
+
+        if let Some(first) = first {
+            for placement in first..self.placements[piece].len() {
+                let mask = &self.placements[piece][placement].mask;
+
+                if !mask.is_subset(&board) {
+                    continue;
+                }
+
+                let rest = &board - mask;
+                self.chosen[piece] = Some(placement);
+                self.search(depth + 1, rest, score + self.placement_weights[piece][placement], area);
+            }
+        }
+
+        self.chosen[piece] = None;
+        self.search(depth + 1, board, score, area);
+    }
+
+    /// Finds the arrangement with the highest score.
+    pub fn solve(&mut self) -> &Arrangement {
+        let area = self.order.iter().map(|&piece| self.game.pieces[piece].len()).sum();
+        self.search(0, self.board.clone(), self.best.score, area);
+        &self.best
+    }
+}
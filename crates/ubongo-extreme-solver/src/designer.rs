@@ -0,0 +1,234 @@
+use crate::{canonicalize_shape, Axial, BoardLayout, Budget, Game, Lattice, RunOutcome, Solver, SolverOptions};
+use indexmap::IndexSet;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{ops::RangeInclusive, time::Duration};
+
+/// A change to a game made by [`design`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Edit {
+    /// Adds a cell of weight one, keeping the board and [`Game::weights`] sorted the same way.
+    AddCell(Axial),
+    /// Removes a cell together with its weight.
+    RemoveCell(Axial),
+    /// Replaces every copy of the piece at the index with a piece of the pool.
+    SwapPiece { piece: usize, shape: Vec<Axial> },
+}
+
+impl Edit {
+    pub fn apply<L: Lattice>(&self, game: &mut Game<L>) {
+        match self {
+            Self::AddCell(cell) => {
+                let mut cells: Vec<(Axial, u32)> = (0..game.board.len()).map(|i| (game.board[i], game.weight(i))).collect();
+                cells.push((*cell, 1));
+                cells.sort_unstable_by_key(|(cell, _)| cell.key());
+
+                game.board = cells.iter().map(|&(cell, _)| cell).collect();
+                if !game.weights.is_empty() {
+                    game.weights = cells.iter().map(|&(_, weight)| weight).collect();
+                }
+            }
+            Self::RemoveCell(cell) => {
+                if let Some(i) = game.board.iter().position(|other| other == cell) {
+                    game.board.remove(i);
+                    if i < game.weights.len() {
+                        game.weights.remove(i);
+                    }
+                }
+            }
+            Self::SwapPiece { piece, shape } => game.pieces[*piece].clone_from(shape),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DesignOptions {
+    /// How many solutions the game should have, counting swaps of identical pieces once.
+    pub target: RangeInclusive<usize>,
+    /// Pieces that may replace the pieces of the game.
+    pub pool: Vec<Vec<Axial>>,
+    pub seed: u64,
+    /// How many edits to try before giving up.
+    pub max_iterations: usize,
+    /// Limits each count, a game whose count runs out of budget is rejected unless it has too many solutions anyway.
+    pub budget: Budget,
+}
+
+/// The outcome of [`design`].
+#[derive(Debug, Clone)]
+pub struct Design<L: Lattice> {
+    pub game: Game<L>,
+    /// The edits that were kept, in the order they were applied.
+    pub edits: Vec<Edit>,
+    pub solutions: usize,
+    pub reached_target: bool,
+    /// How many games were counted, including the initial one.
+    pub counts: usize,
+    /// The solver steps and time spent on counting.
+    pub steps: usize,
+    pub elapsed: Duration,
+}
+
+/// Edits the board and pieces until the game has a number of solutions in the target range.
+///
+/// Each iteration tries a random edit that keeps the area of the board equal to the area of the pieces:
+/// moving a cell of the edge of the board, or swapping a piece and growing or shrinking the board to match.
+/// The edit is kept if the count gets at least as close to the target, and the game stays connected, valid and solvable.
+/// Fixed pieces and the cells they cover are never edited.
+///
+/// Identical pieces can be swapped in every solution, so they only count once.
+pub fn design<L: Lattice>(game: Game<L>, options: &DesignOptions) -> Design<L> {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut this = Design {
+        game,
+        edits: Vec::new(),
+        solutions: 0,
+        reached_target: false,
+        counts: 0,
+        steps: 0,
+        elapsed: Duration::ZERO,
+    };
+
+    let mut solutions = this.count(&this.game.clone(), options);
+    this.solutions = solutions.unwrap_or(0);
+
+    for _ in 0..options.max_iterations {
+        if options.target.contains(&this.solutions) && solutions.is_some() {
+            break;
+        }
+
+        let Some(edits) = propose(&mut rng, &this.game, &options.pool) else {
+            continue;
+        };
+
+        let mut candidate = this.game.clone();
+        edits.iter().for_each(|edit| edit.apply(&mut candidate));
+
+        let Some(count) = this.count(&candidate, options) else {
+            continue;
+        };
+
+        if count > 0 && (solutions.is_none() || distance(&options.target, count) <= distance(&options.target, this.solutions)) {
+            this.game = candidate;
+            this.edits.extend(edits);
+            this.solutions = count;
+            solutions = Some(count);
+        }
+    }
+
+    this.reached_target = solutions.is_some() && options.target.contains(&this.solutions);
+    this
+}
+
+impl<L: Lattice> Design<L> {
+    /// Counts the distinct tilings, `None` if the game is invalid
+    /// or the budget ran out before it is clear the game has too many.
+    fn count(&mut self, game: &Game<L>, options: &DesignOptions) -> Option<usize> {
+        let mut solver = Solver::try_with_options(
+            game.clone(),
+            SolverOptions {
+                count_only: true,
+                distinct_tilings: true,
+                prune_regions: true,
+                ..Default::default()
+            },
+        )
+        .ok()?;
+        let result = solver.run(&options.budget);
+
+        self.counts += 1;
+        self.steps += result.steps;
+        self.elapsed += result.elapsed;
+
+        let solutions = solver.stats.solutions;
+        (result.outcome == RunOutcome::Finished || solutions > *options.target.end()).then_some(solutions)
+    }
+}
+
+/// How many solutions the count is away from the target range.
+fn distance(target: &RangeInclusive<usize>, count: usize) -> usize {
+    target.start().saturating_sub(count) + count.saturating_sub(*target.end())
+}
+
+fn propose<L: Lattice>(rng: &mut StdRng, game: &Game<L>, pool: &[Vec<Axial>]) -> Option<Vec<Edit>> {
+    let mut board = game.board.clone();
+    let mut edits = Vec::new();
+    let mut grow = 0;
+    let mut shrink = 0;
+
+    let fixed: Vec<Axial> = game.fixed.iter().flat_map(|fixed| fixed.cells.iter().copied()).collect();
+    let swappable: Vec<usize> = (0..game.pieces.len())
+        .filter(|&piece| game.fixed.iter().all(|fixed| fixed.piece != piece))
+        .collect();
+
+    if pool.is_empty() || swappable.is_empty() || rng.gen_bool(0.5) {
+        (grow, shrink) = (1, 1);
+    } else {
+        let piece = swappable[rng.gen_range(0..swappable.len())];
+        let mut shape = pool.choose(rng)?.clone();
+        canonicalize_shape::<L>(&mut shape);
+
+        // every copy of the piece is swapped
+        let (old, new) = (game.pieces[piece].len() * game.count(piece), shape.len() * game.count(piece));
+        match new >= old {
+            true => grow = new - old,
+            false => shrink = old - new,
+        }
+
+        edits.push(Edit::SwapPiece { piece, shape });
+    }
+
+    for _ in 0..shrink {
+        let cell = *edge_cells::<L>(&board)
+            .iter()
+            .filter(|&&cell| board.len() > 1 && !fixed.contains(&cell) && is_connected::<L>(board.iter().copied().filter(|&other| other != cell).collect()))
+            .copied()
+            .collect::<Vec<_>>()
+            .choose(rng)?;
+
+        edits.push(Edit::RemoveCell(cell));
+        board.retain(|&other| other != cell);
+    }
+
+    for _ in 0..grow {
+        let removed: Vec<Axial> = edits
+            .iter()
+            .filter_map(|edit| if let Edit::RemoveCell(cell) = edit { Some(*cell) } else { None })
+            .collect();
+        let cell = *outside_cells::<L>(&board)
+            .into_iter()
+            .filter(|cell| !removed.contains(cell))
+            .collect::<Vec<_>>()
+            .choose(rng)?;
+
+        edits.push(Edit::AddCell(cell));
+        board.push(cell);
+    }
+
+    Some(edits)
+}
+
+/// Returns the cells of the board that have a neighbour off the board.
+fn edge_cells<L: Lattice>(board: &[Axial]) -> Vec<Axial> {
+    board
+        .iter()
+        .copied()
+        .filter(|&cell| L::neighbours(cell).iter().any(|neighbour| !board.contains(neighbour)))
+        .collect()
+}
+
+/// Returns the cells off the board that have a neighbour on the board.
+fn outside_cells<L: Lattice>(board: &[Axial]) -> IndexSet<Axial> {
+    board
+        .iter()
+        .flat_map(|&cell| L::neighbours(cell))
+        .filter(|neighbour| !board.contains(neighbour))
+        .collect()
+}
+
+fn is_connected<L: Lattice>(board: Vec<Axial>) -> bool {
+    let layout = BoardLayout::new(&board);
+    let mask = layout.mask(&board).expect("board fits into its own layout");
+    layout.regions(&layout.neighbours::<L>(), mask).count() <= 1
+}
@@ -0,0 +1,287 @@
+use crate::{canonicalize_place, placements, Axial, BoardLayout, Game, GameError, Hex, Lattice, PieceCopy, PieceCount, Placement, Solution};
+use indexmap::IndexSet;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    left: usize,
+    right: usize,
+    up: usize,
+    down: usize,
+    column: usize,
+    row: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    piece: usize,
+    cells: Vec<Axial>,
+}
+
+/// An exact cover solver using Knuth's Dancing Links (Algorithm X).
+///
+/// There is one column per piece and one per board cell. Each piece has to be used exactly once.
+/// If the pieces cover the whole board, so does every cell, otherwise cells may stay empty.
+/// The pieces of a pool may be used at most once instead, and every cell has to be covered.
+///
+/// Produces the same solutions as [`Solver`](crate::Solver), albeit in a different order.
+#[derive(Debug, Clone)]
+pub struct DlxSolver<L: Lattice = Hex> {
+    /// The game with the copies of its pieces listed separately, see [`Game::expand_copies`].
+    pub game: Game<L>,
+    pub copies: Vec<PieceCopy>,
+    pub solutions: IndexSet<Solution>,
+    pub steps: usize,
+    nodes: Vec<Node>,
+    sizes: Vec<usize>,
+    rows: Vec<Row>,
+    chosen: Vec<usize>,
+    layout: BoardLayout,
+}
+
+const ROOT: usize = 0;
+
+impl<L: Lattice> DlxSolver<L> {
+    /// Panics if the game is invalid, see [`Game::validate`].
+    pub fn new(game: Game<L>) -> Self {
+        Self::try_new(game).unwrap_or_else(|error| panic!("invalid game: {error}"))
+    }
+
+    pub fn try_new(game: Game<L>) -> Result<Self, GameError> {
+        game.validate()?;
+
+        let (game, copies) = game.expand_copies();
+        let cells_count = game.board.len();
+        let columns_count = game.pieces.len() + cells_count;
+        let pieces_area: usize = game.pieces.iter().map(Vec::len).sum();
+        let pieces_count = game.pieces.len();
+        let piece_count = game.piece_count;
+        let is_piece = |column: usize| column <= pieces_count;
+        let is_primary = |column: usize| match piece_count {
+            PieceCount::All => is_piece(column) || pieces_area == cells_count,
+            _ => !is_piece(column),
+        };
+
+        let mut this = Self {
+            nodes: Vec::new(),
+            sizes: vec![0; columns_count + 1],
+            rows: Vec::new(),
+            chosen: Vec::new(),
+            solutions: Default::default(),
+            steps: 0,
+            layout: BoardLayout::new(&game.board),
+            copies,
+            game,
+        };
+
+        for column in 0..=columns_count {
+            this.nodes.push(Node {
+                left: column,
+                right: column,
+                up: column,
+                down: column,
+                column,
+                row: usize::MAX,
+            });
+        }
+
+        // the root is column 0, only primary columns are linked into the header list
+        let mut previous = ROOT;
+
+        for column in (1..=columns_count).filter(|&column| is_primary(column)) {
+            this.nodes[previous].right = column;
+            this.nodes[column].left = previous;
+            previous = column;
+        }
+
+        this.nodes[previous].right = ROOT;
+        this.nodes[ROOT].left = previous;
+
+        let cells: IndexSet<Axial> = this.game.board.iter().copied().collect();
+        let mut fixed_cells = vec![None; this.game.pieces.len()];
+
+        for fixed in &this.game.fixed {
+            let mut cells = fixed.cells.clone();
+            canonicalize_place(&mut cells);
+            fixed_cells[fixed.piece] = Some(cells);
+        }
+
+        let covered: HashSet<Axial> = this.game.fixed.iter().flat_map(|fixed| fixed.cells.iter().copied()).collect();
+
+        for Placement { piece, cells: placed, .. } in placements(&this.game) {
+            // a fixed piece only has its fixed placement, which no other piece may overlap
+            let is_allowed = match &fixed_cells[piece] {
+                Some(fixed) => {
+                    let mut cells = placed.clone();
+                    canonicalize_place(&mut cells);
+                    &cells == fixed
+                }
+                None => placed.iter().all(|cell| !covered.contains(cell)),
+            };
+
+            if !is_allowed {
+                continue;
+            }
+
+            let mut columns = vec![1 + piece];
+            columns.extend(placed.iter().map(|cell| {
+                let cell_idx = cells.get_index_of(cell).expect("placements are on the board");
+                1 + this.game.pieces.len() + cell_idx
+            }));
+
+            this.rows.push(Row { piece, cells: placed });
+            this.push_row(&columns);
+        }
+
+        Ok(this)
+    }
+
+    fn push_row(&mut self, columns: &[usize]) {
+        let row = self.rows.len() - 1;
+        let first = self.nodes.len();
+
+        for (i, &column) in columns.iter().enumerate() {
+            let node = self.nodes.len();
+            let up = self.nodes[column].up;
+
+            self.nodes.push(Node {
+                left: if i == 0 { first + columns.len() - 1 } else { node - 1 },
+                right: if i == columns.len() - 1 { first } else { node + 1 },
+                up,
+                down: column,
+                column,
+                row,
+            });
+
+            self.nodes[up].down = node;
+            self.nodes[column].up = node;
+            self.sizes[column] += 1;
+        }
+    }
+
+    fn cover(&mut self, column: usize) {
+        let Node { left, right, .. } = self.nodes[column];
+        self.nodes[left].right = right;
+        self.nodes[right].left = left;
+
+        let mut i = self.nodes[column].down;
+        while i != column {
+            let mut j = self.nodes[i].right;
+            while j != i {
+                let Node { up, down, column, .. } = self.nodes[j];
+                self.nodes[up].down = down;
+                self.nodes[down].up = up;
+                self.sizes[column] -= 1;
+                j = self.nodes[j].right;
+            }
+            i = self.nodes[i].down;
+        }
+    }
+
+    fn uncover(&mut self, column: usize) {
+        let mut i = self.nodes[column].up;
+        while i != column {
+            let mut j = self.nodes[i].left;
+            while j != i {
+                let Node { up, down, column, .. } = self.nodes[j];
+                self.nodes[up].down = j;
+                self.nodes[down].up = j;
+                self.sizes[column] += 1;
+                j = self.nodes[j].left;
+            }
+            i = self.nodes[i].up;
+        }
+
+        let Node { left, right, .. } = self.nodes[column];
+        self.nodes[left].right = column;
+        self.nodes[right].left = column;
+    }
+
+    /// Picks the primary column with the fewest rows.
+    fn choose_column(&self) -> usize {
+        let mut best = self.nodes[ROOT].right;
+        let mut column = best;
+
+        while column != ROOT {
+            if self.sizes[column] < self.sizes[best] {
+                best = column;
+            }
+            column = self.nodes[column].right;
+        }
+
+        best
+    }
+
+    fn record_solution(&mut self) {
+        if let PieceCount::Exactly(count) = self.game.piece_count {
+            if self.chosen.len() != count {
+                return;
+            }
+        }
+
+        // pieces of a pool that are left unused have no cells
+        let mut pieces = vec![Vec::new(); self.game.pieces.len()];
+
+        for &row in &self.chosen {
+            let Row { piece, cells } = &self.rows[row];
+            let mut cells = cells.clone();
+            canonicalize_place(&mut cells);
+            pieces[*piece] = cells;
+        }
+
+        // copies of a piece are interchangeable, order them like the solver does
+        let mut first = 0;
+
+        for copies in self.copies.chunk_by(|a, b| a.piece == b.piece) {
+            pieces[first..first + copies.len()].sort_by_cached_key(|cells| {
+                let mask = self.layout.mask(cells).expect("placements are on the board");
+                (mask.is_zero(), mask)
+            });
+            first += copies.len();
+        }
+
+        self.solutions.insert(pieces);
+    }
+
+    fn search(&mut self) {
+        self.steps += 1;
+
+        if self.nodes[ROOT].right == ROOT {
+            self.record_solution();
+            return;
+        }
+
+        let column = self.choose_column();
+        self.cover(column);
+
+        let mut r = self.nodes[column].down;
+        while r != column {
+            self.chosen.push(self.nodes[r].row);
+
+            let mut j = self.nodes[r].right;
+            while j != r {
+                self.cover(self.nodes[j].column);
+                j = self.nodes[j].right;
+            }
+
+            self.search();
+
+            let mut j = self.nodes[r].left;
+            while j != r {
+                self.uncover(self.nodes[j].column);
+                j = self.nodes[j].left;
+            }
+
+            self.chosen.pop();
+            r = self.nodes[r].down;
+        }
+
+        self.uncover(column);
+    }
+
+    /// Finds all solutions.
+    pub fn solve(&mut self) -> &IndexSet<Solution> {
+        self.search();
+        &self.solutions
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{canonicalize_shape, piece_permutations, Axial, Game, Lattice};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt};
+
+/// A piece that is already on the board, like a piece the puzzle starts with or the progress of a player.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct FixedPlacement {
+    /// The index of the piece in [`Game::pieces`], a piece with several copies can be fixed once per copy.
+    pub piece: usize,
+    /// The absolute cells the piece covers.
+    pub cells: Vec<Axial>,
+}
+
+/// Why a [`Game`] can't be solved as given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    /// A fixed placement refers to a piece that does not exist.
+    UnknownPiece { piece: usize },
+    /// A piece is fixed more often than it has copies.
+    TooManyFixed { piece: usize },
+    /// The cells of a fixed placement are not the piece in one of its allowed orientations.
+    WrongShape { piece: usize },
+    /// A fixed placement covers a cell that is not part of the board.
+    OffBoard { piece: usize, cell: Axial },
+    /// Two fixed placements cover the same cell.
+    Overlap { cell: Axial },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPiece { piece } => write!(f, "piece {piece} is fixed, but does not exist"),
+            Self::TooManyFixed { piece } => write!(f, "piece {piece} is fixed more often than it has copies"),
+            Self::WrongShape { piece } => write!(f, "the fixed cells of piece {piece} don't have its shape"),
+            Self::OffBoard { piece, cell } => write!(f, "piece {piece} is fixed onto {cell:?}, which is not on the board"),
+            Self::Overlap { cell } => write!(f, "several fixed pieces cover {cell:?}"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl<L: Lattice> Game<L> {
+    /// Checks that the fixed placements fit onto the board without overlapping.
+    pub fn validate(&self) -> Result<(), GameError> {
+        let board: HashSet<Axial> = self.board.iter().copied().collect();
+        let mut covered = HashSet::new();
+        let mut fixed_copies = vec![0; self.pieces.len()];
+
+        for &FixedPlacement { piece, ref cells } in &self.fixed {
+            let Some(fixed) = fixed_copies.get_mut(piece) else {
+                return Err(GameError::UnknownPiece { piece });
+            };
+
+            *fixed += 1;
+
+            if *fixed > self.count(piece) {
+                return Err(GameError::TooManyFixed { piece });
+            }
+
+            let mut shape = cells.clone();
+            canonicalize_shape::<L>(&mut shape);
+
+            if !piece_permutations::<L>(self.pieces[piece].clone(), self.orientation(piece)).contains(&shape) {
+                return Err(GameError::WrongShape { piece });
+            }
+
+            for &cell in cells {
+                if !board.contains(&cell) {
+                    return Err(GameError::OffBoard { piece, cell });
+                }
+
+                if !covered.insert(cell) {
+                    return Err(GameError::Overlap { cell });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
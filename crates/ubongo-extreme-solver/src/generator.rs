@@ -0,0 +1,149 @@
+use crate::{canonicalize_place, canonicalize_shape, translate, Axial, Budget, Game, Lattice, RunOutcome, Solution, SolverOptions, Transform};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{collections::HashSet, ops::RangeInclusive};
+
+/// Where the pieces of a generated puzzle come from.
+#[derive(Debug, Clone)]
+pub enum PieceSource {
+    /// Picks `count` different pieces of the pool.
+    Pool { pieces: Vec<Vec<Axial>>, count: usize },
+    /// Grows `count` random pieces, each with a size in `sizes`.
+    Random { count: usize, sizes: RangeInclusive<usize> },
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorOptions {
+    pub pieces: PieceSource,
+    pub seed: u64,
+    /// How many boards to build before giving up, if the budget runs out before a solution is found.
+    pub max_attempts: usize,
+    /// Limits the search that counts the solutions.
+    pub budget: Budget,
+    /// The options of the counting solver, `count_only` is always enabled.
+    pub solver_options: SolverOptions,
+}
+
+/// A puzzle made by [`generate`].
+#[derive(Debug, Clone)]
+pub struct GeneratedPuzzle<L: Lattice> {
+    pub game: Game<L>,
+    /// The arrangement of the pieces the board was built from.
+    pub solution: Solution,
+    pub solutions: usize,
+    /// Whether `solutions` counts all solutions, rather than those found before the budget ran out.
+    pub counted_all: bool,
+}
+
+/// Builds a board by placing the pieces one after another, each where it touches the most cells of the board so far.
+///
+/// The board is connected and has at least one solution, which the solver confirms when it counts them.
+/// Returns `None` if no attempt found a solution within the budget.
+pub fn generate<L: Lattice>(options: &GeneratorOptions) -> Option<GeneratedPuzzle<L>> {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    for _ in 0..options.max_attempts {
+        let pieces = match &options.pieces {
+            PieceSource::Pool { pieces, count } => pieces.choose_multiple(&mut rng, *count).cloned().collect(),
+            PieceSource::Random { count, sizes } => (0..*count)
+                .map(|_| {
+                    let size = rng.gen_range(sizes.clone());
+                    random_piece::<L>(&mut rng, size)
+                })
+                .collect(),
+        };
+
+        let (game, solution) = build_board::<L>(&mut rng, pieces);
+        let mut solver = game.clone().solver_with_options(SolverOptions {
+            count_only: true,
+            ..options.solver_options
+        });
+        let result = solver.run(&options.budget);
+
+        if solver.stats.solutions > 0 {
+            return Some(GeneratedPuzzle {
+                game,
+                solution,
+                solutions: solver.stats.solutions,
+                counted_all: result.outcome == RunOutcome::Finished,
+            });
+        }
+    }
+
+    None
+}
+
+/// Grows a piece from a single cell by adding random neighbours.
+fn random_piece<L: Lattice>(rng: &mut StdRng, size: usize) -> Vec<Axial> {
+    let mut piece = vec![Axial::ZERO];
+
+    while piece.len() < size {
+        let cell = *piece.choose(rng).expect("pieces have a cell");
+        let neighbour = *L::neighbours(cell).choose(rng).expect("cells have neighbours");
+
+        if !piece.contains(&neighbour) {
+            piece.push(neighbour);
+        }
+    }
+
+    canonicalize_shape::<L>(&mut piece);
+    piece
+}
+
+fn build_board<L: Lattice>(rng: &mut StdRng, mut pieces: Vec<Vec<Axial>>) -> (Game<L>, Solution) {
+    let transforms: Vec<Transform> = Transform::all::<L>().collect();
+    let mut board: HashSet<Axial> = HashSet::new();
+    let mut solution = vec![Vec::new(); pieces.len()];
+
+    for piece in &mut pieces {
+        canonicalize_shape::<L>(piece);
+    }
+
+    let mut order: Vec<usize> = (0..pieces.len()).collect();
+    order.shuffle(rng);
+
+    for piece in order {
+        let transform = *transforms.choose(rng).expect("there is an identity");
+        let mut cells: Vec<Axial> = pieces[piece].iter().map(|&cell| transform.apply::<L>(cell)).collect();
+        canonicalize_shape::<L>(&mut cells);
+
+        // translations that move a cell of the piece onto a free neighbour of the board
+        let mut candidates: Vec<Axial> = board
+            .iter()
+            .flat_map(|&cell| L::neighbours(cell))
+            .filter(|neighbour| !board.contains(neighbour))
+            .flat_map(|neighbour| cells.iter().map(move |&cell| neighbour - cell))
+            .filter(|&translation| L::is_translation(translation))
+            .filter(|&translation| cells.iter().all(|&cell| !board.contains(&(cell + translation))))
+            .collect();
+
+        candidates.sort_by_key(|translation| translation.key());
+        candidates.dedup();
+        candidates.shuffle(rng);
+
+        // the translation with the most contacts keeps the board compact
+        let contacts = |translation: Axial| {
+            cells
+                .iter()
+                .flat_map(|&cell| L::neighbours(cell + translation))
+                .filter(|neighbour| board.contains(neighbour))
+                .count()
+        };
+        let translation = candidates.into_iter().max_by_key(|&translation| contacts(translation)).unwrap_or(Axial::ZERO);
+
+        translate(&mut cells, translation);
+        canonicalize_place(&mut cells);
+        board.extend(cells.iter().copied());
+        solution[piece] = cells;
+    }
+
+    let mut board: Vec<Axial> = board.into_iter().collect();
+    canonicalize_place(&mut board);
+
+    let game = Game {
+        board,
+        pieces,
+        ..Default::default()
+    };
+
+    (game, solution)
+}
@@ -0,0 +1,187 @@
+use crate::Axial;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, hash::Hash};
+
+/// A grid of cells addressed by pairs of integers, together with its symmetry group.
+///
+/// The symmetries are generated by a rotation and a reflection around the origin.
+pub trait Lattice: Debug + Default + Clone + Copy + PartialEq + Eq + Hash + Send + Sync + 'static {
+    /// The order of the rotation, so the group has twice as many elements.
+    const ROTATIONS: u8;
+
+    /// The name of the lattice in the `lattice` field of the JSON of a [`Game`](crate::Game).
+    const NAME: &'static str;
+
+    /// Rotates a cell by one step around the origin.
+    fn rotate(coord: Axial) -> Axial;
+
+    /// Mirrors a cell at an axis through the origin.
+    fn flip(coord: Axial) -> Axial;
+
+    /// Returns the cells that share an edge with the cell.
+    fn neighbours(coord: Axial) -> Vec<Axial>;
+
+    /// Returns whether moving every cell by `offset` maps the lattice onto itself.
+    fn is_translation(offset: Axial) -> bool {
+        let _ = offset;
+        true
+    }
+
+    /// Returns the translation that moves a shape with the component-wise minimum `min` next to the origin.
+    fn to_origin(min: Axial) -> Axial {
+        Axial::ZERO - min
+    }
+
+    /// Rotates a cell of a stack of layers by a quarter turn around a horizontal axis,
+    /// if the cells are cubes and such a turn maps the stack onto the lattice.
+    ///
+    /// Turning a piece upside down is always possible, it is a flip that also mirrors the layers.
+    fn tilt(coord: Axial, layer: i32) -> Option<(Axial, i32)> {
+        let _ = (coord, layer);
+        None
+    }
+
+    #[must_use]
+    fn rotate_many(coord: Axial, steps: usize) -> Axial {
+        (0..steps % Self::ROTATIONS as usize).fold(coord, |coord, _| Self::rotate(coord))
+    }
+}
+
+/// Hexagons in axial coordinates, the board of Ubongo Extreme.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hex;
+
+impl Lattice for Hex {
+    const ROTATIONS: u8 = 6;
+    const NAME: &'static str = "hex";
+
+    fn rotate(coord: Axial) -> Axial {
+        coord.rotate(Axial::ZERO)
+    }
+
+    fn flip(coord: Axial) -> Axial {
+        coord.flip()
+    }
+
+    fn neighbours(coord: Axial) -> Vec<Axial> {
+        coord.neighbours().collect()
+    }
+
+    fn rotate_many(coord: Axial, steps: usize) -> Axial {
+        coord.rotate_many(Axial::ZERO, steps)
+    }
+}
+
+/// Squares in column and row coordinates, the board of classic Ubongo.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Square;
+
+impl Square {
+    pub const DIRECTIONS: [Axial; 4] = [Axial(1, 0), Axial(0, 1), Axial(-1, 0), Axial(0, -1)];
+}
+
+impl Lattice for Square {
+    const ROTATIONS: u8 = 4;
+    const NAME: &'static str = "square";
+
+    fn rotate(Axial(x, y): Axial) -> Axial {
+        Axial(-y, x)
+    }
+
+    fn flip(Axial(x, y): Axial) -> Axial {
+        Axial(-x, y)
+    }
+
+    fn neighbours(coord: Axial) -> Vec<Axial> {
+        Self::DIRECTIONS.iter().map(|&direction| coord + direction).collect()
+    }
+
+    fn tilt(Axial(x, y): Axial, layer: i32) -> Option<(Axial, i32)> {
+        Some((Axial(x, -layer), y))
+    }
+}
+
+/// Triangles, the board of polyiamond puzzles.
+///
+/// The corners of the triangles are the centers of the hexagonal lattice, so they share its symmetry group.
+/// A cell is stored as `Axial(2 * q + 1, r)` if it is the down triangle of the corner `Axial(q, r)`,
+/// and as `Axial(2 * q, r)` if it is the up triangle, see [`TriangleCell`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Triangle;
+
+/// A cell of the [`Triangle`] lattice.
+///
+/// The up triangle of a corner has the corners `corner`, `corner + Axial(1, 0)` and `corner + Axial(0, 1)`,
+/// the down triangle has `corner + Axial(1, 0)`, `corner + Axial(0, 1)` and `corner + Axial(1, 1)`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TriangleCell {
+    pub corner: Axial,
+    pub up: bool,
+}
+
+impl From<Axial> for TriangleCell {
+    fn from(Axial(x, y): Axial) -> Self {
+        Self {
+            corner: Axial(x.div_euclid(2), y),
+            up: x.rem_euclid(2) == 0,
+        }
+    }
+}
+
+impl From<TriangleCell> for Axial {
+    fn from(TriangleCell { corner, up }: TriangleCell) -> Self {
+        Axial(2 * corner.0 + i32::from(!up), corner.1)
+    }
+}
+
+impl TriangleCell {
+    /// Returns three times the center of the triangle, which is the sum of its corners.
+    fn center3(self) -> Axial {
+        let k = if self.up { 1 } else { 2 };
+        Axial(3 * self.corner.0 + k, 3 * self.corner.1 + k)
+    }
+
+    fn from_center3(center3: Axial) -> Self {
+        let k = center3.0.rem_euclid(3);
+        debug_assert_eq!(k, center3.1.rem_euclid(3), "not the center of a triangle");
+
+        Self {
+            corner: Axial((center3.0 - k) / 3, (center3.1 - k) / 3),
+            up: k == 1,
+        }
+    }
+}
+
+impl Lattice for Triangle {
+    const ROTATIONS: u8 = 6;
+    const NAME: &'static str = "triangle";
+
+    fn rotate(coord: Axial) -> Axial {
+        let center3 = TriangleCell::from(coord).center3();
+        TriangleCell::from_center3(center3.rotate(Axial::ZERO)).into()
+    }
+
+    fn flip(coord: Axial) -> Axial {
+        let center3 = TriangleCell::from(coord).center3();
+        TriangleCell::from_center3(center3.flip()).into()
+    }
+
+    fn neighbours(coord: Axial) -> Vec<Axial> {
+        let TriangleCell { corner, up } = coord.into();
+        let (sign, up) = if up { (-1, false) } else { (1, true) };
+
+        [Axial::ZERO, Axial(sign, 0), Axial(0, sign)]
+            .into_iter()
+            .map(|offset| TriangleCell { corner: corner + offset, up }.into())
+            .collect()
+    }
+
+    /// Translations have to map up triangles to up triangles.
+    fn is_translation(offset: Axial) -> bool {
+        offset.0.rem_euclid(2) == 0
+    }
+
+    fn to_origin(min: Axial) -> Axial {
+        Axial::ZERO - Axial(min.0 - min.0.rem_euclid(2), min.1)
+    }
+}
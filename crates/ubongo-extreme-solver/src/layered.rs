@@ -0,0 +1,226 @@
+use crate::{canonicalize_shape, min, Axial, Checkpoint, CheckpointError, Game, Hex, Lattice, PieceCount, Solution, Solver, SolverOptions};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// A cell in a stack of layers, layer 0 is the bottom.
+///
+/// Serialized as `[x, y, layer]`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(from = "(i32, i32, i32)", into = "(i32, i32, i32)")]
+pub struct Voxel {
+    pub cell: Axial,
+    pub layer: i32,
+}
+
+impl From<(i32, i32, i32)> for Voxel {
+    fn from((x, y, layer): (i32, i32, i32)) -> Self {
+        Self { cell: Axial(x, y), layer }
+    }
+}
+
+impl From<Voxel> for (i32, i32, i32) {
+    fn from(Voxel { cell: Axial(x, y), layer }: Voxel) -> Self {
+        (x, y, layer)
+    }
+}
+
+/// The cells of every piece, in the order of [`LayeredGame::pieces`].
+pub type LayeredSolution = Vec<Vec<Voxel>>;
+
+/// A footprint that has to be filled with pieces up to a height of `layers`, like in Ubongo 3D.
+///
+/// On the [`Square`](crate::Square) lattice the pieces are polycubes, on the others they are stacks of flat cells.
+/// Either way, they may take every orientation that rotating them in space allows.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(bound = "")]
+pub struct LayeredGame<L: Lattice = Hex> {
+    pub footprint: Vec<Axial>,
+    pub layers: i32,
+    pub pieces: Vec<Vec<Voxel>>,
+    #[serde(skip)]
+    pub lattice: PhantomData<L>,
+}
+
+impl<L: Lattice> LayeredGame<L> {
+    /// Returns every cell of the footprint on every layer, from the bottom up.
+    pub fn board(&self) -> Vec<Voxel> {
+        (0..self.layers)
+            .flat_map(|layer| self.footprint.iter().map(move |&cell| Voxel { cell, layer }))
+            .collect()
+    }
+
+    pub fn solver(self) -> LayeredSolver<L> {
+        LayeredSolver::new(self, Default::default())
+    }
+
+    pub fn solver_with_options(self, options: SolverOptions) -> LayeredSolver<L> {
+        LayeredSolver::new(self, options)
+    }
+}
+
+/// Moves the piece next to the origin and onto the bottom layer, so pieces of the same shape and orientation become equal.
+pub fn canonicalize_voxels<L: Lattice>(piece: &mut [Voxel]) {
+    let cells: Vec<Axial> = piece.iter().map(|voxel| voxel.cell).collect();
+    let offset = L::to_origin(min(&cells));
+    let bottom = piece.iter().map(|voxel| voxel.layer).min().unwrap_or(0);
+
+    for voxel in piece.iter_mut() {
+        voxel.cell += offset;
+        voxel.layer -= bottom;
+    }
+
+    piece.sort_unstable_by_key(|voxel| (voxel.layer, voxel.cell.key()));
+}
+
+/// Returns the distinct shapes the piece takes when rotated in space, canonicalized, starting with the piece as given.
+pub fn voxel_orientations<L: Lattice>(piece: &[Voxel]) -> Vec<Vec<Voxel>> {
+    // the rotations in space are generated by these turns
+    let turns: [fn(Voxel) -> Option<Voxel>; 3] = [
+        |Voxel { cell, layer }| Some(Voxel { cell: L::rotate(cell), layer }),
+        |Voxel { cell, layer }| {
+            Some(Voxel {
+                cell: L::flip(cell),
+                layer: -layer,
+            })
+        },
+        |Voxel { cell, layer }| L::tilt(cell, layer).map(|(cell, layer)| Voxel { cell, layer }),
+    ];
+
+    let mut first = piece.to_vec();
+    canonicalize_voxels::<L>(&mut first);
+    let mut orientations = vec![first];
+    let mut i = 0;
+
+    while i < orientations.len() {
+        for turn in turns {
+            let Some(mut turned) = orientations[i].iter().map(|&voxel| turn(voxel)).collect::<Option<Vec<_>>>() else {
+                continue;
+            };
+            canonicalize_voxels::<L>(&mut turned);
+
+            if !orientations.contains(&turned) {
+                orientations.push(turned);
+            }
+        }
+
+        i += 1;
+    }
+
+    orientations
+}
+
+/// Solves a [`LayeredGame`] by laying its layers out next to each other in the plane and searching that with a [`Solver`].
+///
+/// The layers are far enough apart that a piece only fits across them as a whole.
+/// Symmetries and unfillable regions are not detected in this layout, so `dedup_symmetries` and `prune_regions` are ignored.
+#[derive(Debug, Clone)]
+pub struct LayeredSolver<L: Lattice = Hex> {
+    pub game: LayeredGame<L>,
+    /// The solver of the flattened game, whose solutions are decoded by [`Self::decode`].
+    pub solver: Solver<L>,
+    /// The distance between two layers in the flattened layout.
+    stride: i32,
+    /// The first column of the footprint.
+    first_column: i32,
+}
+
+impl<L: Lattice> LayeredSolver<L> {
+    pub fn new(game: LayeredGame<L>, options: SolverOptions) -> Self {
+        let orientations: Vec<Vec<Vec<Voxel>>> = game.pieces.iter().map(|piece| voxel_orientations::<L>(piece)).collect();
+        let (first_column, width) = columns(game.footprint.iter().copied());
+        let widest = orientations
+            .iter()
+            .flatten()
+            .map(|orientation| columns(orientation.iter().map(|voxel| voxel.cell)).1)
+            .max()
+            .unwrap_or(0);
+        // even, so that translations between the layers keep the parity of the triangle lattice
+        let stride = 2 * (width.max(widest) + 1);
+        let flatten = move |Voxel { cell, layer }: Voxel| Axial(cell.0 + layer * stride, cell.1);
+
+        let board: Vec<Axial> = game.board().into_iter().map(flatten).collect();
+        let area: usize = game.pieces.iter().map(Vec::len).sum();
+        let flat = Game {
+            pieces: orientations
+                .iter()
+                .map(|orientations| orientations[0].iter().copied().map(flatten).collect())
+                .collect(),
+            // with all pieces placed, a pool can't leave cells empty
+            piece_count: match area == board.len() {
+                true => PieceCount::All,
+                false => PieceCount::Exactly(game.pieces.len()),
+            },
+            board,
+            ..Default::default()
+        };
+
+        let permutations = |_: &Game<L>| {
+            orientations
+                .iter()
+                .map(|orientations| {
+                    orientations
+                        .iter()
+                        .map(|orientation| {
+                            let mut permutation: Vec<Axial> = orientation.iter().copied().map(flatten).collect();
+                            canonicalize_shape::<L>(&mut permutation);
+                            permutation
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+
+        let options = SolverOptions {
+            dedup_symmetries: false,
+            prune_regions: false,
+            ..options
+        };
+        let solver = Solver::try_with_permutations(flat, options, permutations).expect("layered games have no fixed pieces");
+
+        Self {
+            game,
+            solver,
+            stride,
+            first_column,
+        }
+    }
+
+    /// Continues a search of the game where [`Solver::checkpoint`] of [`Self::solver`] left it.
+    pub fn resume(game: LayeredGame<L>, checkpoint: Checkpoint<L>) -> Result<Self, CheckpointError> {
+        let mut this = Self::new(game, checkpoint.options);
+        this.solver = Solver::resume(checkpoint)?;
+        Ok(this)
+    }
+
+    /// Returns the cells of a solution of [`Self::solver`] with their layers.
+    pub fn decode(&self, solution: &Solution) -> LayeredSolution {
+        let decode = |Axial(x, y): Axial| {
+            let layer = (x - self.first_column).div_euclid(self.stride);
+            Voxel {
+                cell: Axial(x - layer * self.stride, y),
+                layer,
+            }
+        };
+
+        solution.iter().map(|piece| piece.iter().copied().map(decode).collect()).collect()
+    }
+
+    pub fn solutions(&self) -> impl Iterator<Item = LayeredSolution> + '_ {
+        self.solver.solutions.iter().map(|solution| self.decode(solution))
+    }
+
+    /// Finds all remaining solutions and returns all solutions found.
+    pub fn solve(&mut self) -> Vec<LayeredSolution> {
+        self.solver.by_ref().for_each(drop);
+        self.solutions().collect()
+    }
+}
+
+/// Returns the first column of the cells and how many columns they span.
+fn columns(cells: impl Iterator<Item = Axial>) -> (i32, i32) {
+    let (first, last) = cells.fold((i32::MAX, i32::MIN), |(first, last), cell| (first.min(cell.0), last.max(cell.0)));
+    match first <= last {
+        true => (first, last - first + 1),
+        false => (0, 0),
+    }
+}
@@ -1,19 +1,51 @@
-#![feature(type_alias_impl_trait)]
 #![allow(clippy::len_without_is_empty)]
 
 mod axial;
+mod bitboard;
+mod budget;
+mod checkpoint;
+mod coverage;
 mod cube;
+mod designer;
+mod dlx;
+mod fixed;
+mod generator;
+mod lattice;
+mod layered;
+mod parallel;
+mod placement;
+mod symmetry;
 
 pub use axial::{Axial, AxialAabb};
+pub use bitboard::{Bitboard, BoardLayout};
+pub use budget::{Budget, CancelToken, RunOutcome, RunResult};
+pub use checkpoint::{Checkpoint, CheckpointError};
+pub use coverage::{Arrangement, CoverageSolver};
 pub use cube::Cube;
+pub use designer::{design, Design, DesignOptions, Edit};
+pub use dlx::DlxSolver;
+pub use fixed::{FixedPlacement, GameError};
+pub use generator::{generate, GeneratedPuzzle, GeneratorOptions, PieceSource};
+pub use lattice::{Hex, Lattice, Square, Triangle, TriangleCell};
+pub use layered::{canonicalize_voxels, voxel_orientations, LayeredGame, LayeredSolution, LayeredSolver, Voxel};
+pub use placement::{placements, Placement};
+pub use symmetry::{board_symmetries, Orientation, Symmetry, Transform};
 
 #[cfg(test)]
 mod tests;
 
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 
-use std::fmt::Debug;
+use std::{
+    cmp::Reverse,
+    collections::VecDeque,
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    mem::{size_of, size_of_val},
+    time::Duration,
+};
 
 pub fn translate(coords: &mut [Axial], offset: Axial) {
     for coord in coords {
@@ -21,21 +53,27 @@ pub fn translate(coords: &mut [Axial], offset: Axial) {
     }
 }
 
-pub fn rotate(coords: &mut [Axial]) {
+pub fn rotate<L: Lattice>(coords: &mut [Axial]) {
     for coord in coords {
-        *coord = coord.rotate(Axial::ZERO);
+        *coord = L::rotate(*coord);
     }
 }
 
-pub fn rotate_many(coords: &mut [Axial], count: usize) {
+pub fn rotate_many<L: Lattice>(coords: &mut [Axial], count: usize) {
     for coord in coords {
-        *coord = coord.rotate_many(Axial::ZERO, count);
+        *coord = L::rotate_many(*coord, count);
     }
 }
 
-pub fn flip(coords: &mut [Axial]) {
+pub fn flip<L: Lattice>(coords: &mut [Axial]) {
     for coord in coords {
-        *coord = coord.flip();
+        *coord = L::flip(*coord);
+    }
+}
+
+pub fn transform<L: Lattice>(coords: &mut [Axial], transform: Transform) {
+    for coord in coords {
+        *coord = transform.apply::<L>(*coord);
     }
 }
 
@@ -74,229 +112,1259 @@ pub fn spot_candidates(board: &[Axial], piece: &[Axial]) -> impl Iterator<Item =
     (0..max_x).flat_map(move |x| (0..max_y).map(move |y| Axial(x, y) + offset))
 }
 
-pub type Spots = impl Iterator<Item = Axial> + Clone;
+/// Iterates over the translations that place a piece onto the free cells of a board.
+///
+/// Yields the translation together with the mask of the translated piece.
+/// Only translations that map the lattice onto itself are considered.
+#[derive(Debug, Default, Clone)]
+pub struct Spots<L: Lattice = Hex> {
+    board: Bitboard,
+    piece: Bitboard,
+    layout: BoardLayout,
+    offset: Axial,
+    wiggle: Axial,
+    x: i32,
+    y: i32,
+    lattice: PhantomData<L>,
+}
+
+impl<L: Lattice> Spots<L> {
+    pub fn new(layout: BoardLayout, board: Bitboard, piece: &[Axial]) -> Self {
+        let piece_aabb = aabb(piece);
+        let wiggle = layout.size() - piece_aabb.size() - Axial(1, 1);
 
-pub fn spots(board: &[Axial], piece: &[Axial]) -> Spots {
-    let board_clone = board.to_vec();
-    let piece_clone = piece.to_vec();
-    spot_candidates(board, piece).filter(move |spot| {
-        piece_clone.iter().all(|cell| {
-            let translated = *cell + *spot;
-            board_clone.contains(&translated)
-        })
-    })
+        if piece.is_empty() || wiggle.0 < 0 || wiggle.1 < 0 {
+            return Self::default();
+        }
+
+        let offset = layout.min - piece_aabb.min;
+        let mut piece_at_min = piece.to_vec();
+        translate(&mut piece_at_min, offset);
+
+        Self {
+            board,
+            piece: layout.mask(&piece_at_min).expect("piece fits into the layout"),
+            layout,
+            offset,
+            wiggle,
+            x: 0,
+            y: 0,
+            lattice: PhantomData,
+        }
+    }
+}
+
+impl<L: Lattice> Iterator for Spots<L> {
+    type Item = (Axial, Bitboard);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.x <= self.wiggle.0 && !self.piece.is_zero() {
+            let (x, y) = (self.x, self.y);
+
+            if y < self.wiggle.1 {
+                self.y += 1;
+            } else {
+                self.x += 1;
+                self.y = 0;
+            }
+
+            let translation = Axial(x, y) + self.offset;
+
+            if !L::is_translation(translation) {
+                continue;
+            }
+
+            let mask = &self.piece << (x + y * self.layout.width) as usize;
+
+            if mask.is_subset(&self.board) {
+                return Some((translation, mask));
+            }
+        }
+
+        None
+    }
+}
+
+pub fn spots<L: Lattice>(board: &[Axial], piece: &[Axial]) -> Spots<L> {
+    let layout = BoardLayout::new(board);
+    let board = layout.mask(board).expect("board fits into its own layout");
+    Spots::new(layout, board, piece)
 }
 
-pub fn place(board: &mut Vec<Axial>, piece: &[Axial]) {
-    board.retain(|coord| !piece.contains(coord))
+pub fn place(board: &mut Bitboard, piece: &Bitboard) {
+    debug_assert!(piece.is_subset(board), "piece must be placed onto free cells");
+    *board -= piece;
 }
 
 pub fn canonicalize_place(piece: &mut [Axial]) {
     piece.sort_unstable_by_key(|&axial| axial.key())
 }
 
-pub fn canonicalize_shape(piece: &mut [Axial]) {
-    let min = min(piece);
-    for coord in piece.iter_mut() {
-        *coord -= min;
-    }
+/// Moves the piece next to the origin, so pieces of the same shape and orientation become equal.
+pub fn canonicalize_shape<L: Lattice>(piece: &mut [Axial]) {
+    translate(piece, L::to_origin(min(piece)));
     canonicalize_place(piece);
 }
 
+/// The cells of every piece, in the order of [`Game::pieces`].
+pub type Solution = Vec<Vec<Axial>>;
+
+/// A board and the pieces to place on it, with coordinates on the lattice `L`.
+///
+/// The JSON format is the same for every lattice, games on other lattices than [`Hex`] are tagged with [`Lattice::NAME`].
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
-pub struct Game {
+#[serde(bound = "")]
+pub struct Game<L: Lattice = Hex> {
     pub board: Vec<Axial>,
     pub pieces: Vec<Vec<Axial>>,
+    /// The orientation policy of each piece, pieces without an entry are [`Orientation::Free`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orientations: Vec<Orientation>,
+    #[serde(default, skip_serializing_if = "PieceCount::is_all")]
+    pub piece_count: PieceCount,
+    /// The weight of each cell of the board when maximising coverage, cells without an entry weigh one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weights: Vec<u32>,
+    /// How many copies there are of each piece, pieces without an entry have one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub counts: Vec<usize>,
+    /// Pieces that are already placed, solutions only complete the arrangement.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixed: Vec<FixedPlacement>,
+    /// Written as the name of the lattice unless it is [`Hex`], reading a game tagged with another lattice fails.
+    #[serde(
+        default,
+        skip_serializing_if = "is_hex",
+        serialize_with = "serialize_lattice",
+        deserialize_with = "deserialize_lattice"
+    )]
+    pub lattice: PhantomData<L>,
+}
+
+fn is_hex<L: Lattice>(_: &PhantomData<L>) -> bool {
+    L::NAME == Hex::NAME
+}
+
+fn serialize_lattice<L: Lattice, S: serde::Serializer>(_: &PhantomData<L>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(L::NAME)
+}
+
+fn deserialize_lattice<'de, L: Lattice, D: serde::Deserializer<'de>>(deserializer: D) -> Result<PhantomData<L>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    if name != L::NAME {
+        return Err(serde::de::Error::custom(format_args!(
+            "expected a game on the {} lattice, got {name:?}",
+            L::NAME
+        )));
+    }
+    Ok(PhantomData)
 }
 
-impl Game {
+/// Identifies a piece of a game whose pieces have been expanded into their copies.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PieceCopy {
+    /// The index of the piece in [`Game::pieces`] before the expansion.
+    pub piece: usize,
+    pub copy: usize,
+}
+
+/// How many of the pieces of a [`Game`] a solution uses.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PieceCount {
+    /// Every piece is used, cells may stay empty if the pieces are too small for the board.
+    #[default]
+    All,
+    /// The pieces are a pool, every subset of this many pieces that exactly covers the board is a solution.
+    Exactly(usize),
+    /// The pieces are a pool, every subset that exactly covers the board is a solution.
+    Any,
+}
+
+impl PieceCount {
+    pub fn is_all(&self) -> bool {
+        *self == Self::All
+    }
+
+    pub fn is_pool(self) -> bool {
+        !self.is_all()
+    }
+}
+
+/// Returns the pieces a solution uses, unused pieces of a pool have no cells.
+pub fn used_pieces(solution: &[Vec<Axial>]) -> impl Iterator<Item = usize> + '_ {
+    (0..solution.len()).filter(|&piece| !solution[piece].is_empty())
+}
+
+impl<L: Lattice> Game<L> {
     pub const fn new() -> Self {
         Self {
             board: Vec::new(),
             pieces: Vec::new(),
+            orientations: Vec::new(),
+            piece_count: PieceCount::All,
+            weights: Vec::new(),
+            counts: Vec::new(),
+            fixed: Vec::new(),
+            lattice: PhantomData,
         }
     }
 
-    pub fn solver(self) -> Solver {
+    /// Returns the weight of the cell at the given index of the board.
+    pub fn weight(&self, cell: usize) -> u32 {
+        self.weights.get(cell).copied().unwrap_or(1)
+    }
+
+    pub fn count(&self, piece: usize) -> usize {
+        self.counts.get(piece).copied().unwrap_or(1)
+    }
+
+    /// Lists every copy of a piece as a piece of its own and returns which copy each of them is.
+    ///
+    /// Fixed placements are assigned to the copies of their piece in order.
+    pub fn expand_copies(&self) -> (Self, Vec<PieceCopy>) {
+        let copies: Vec<PieceCopy> = (0..self.pieces.len())
+            .flat_map(|piece| (0..self.count(piece)).map(move |copy| PieceCopy { piece, copy }))
+            .collect();
+
+        let mut fixed_copies = vec![0; self.pieces.len()];
+        let fixed = self
+            .fixed
+            .iter()
+            .map(|fixed| {
+                let copy = PieceCopy {
+                    piece: fixed.piece,
+                    copy: fixed_copies[fixed.piece],
+                };
+                fixed_copies[fixed.piece] += 1;
+
+                FixedPlacement {
+                    piece: copies.iter().position(|&other| other == copy).expect("the game has been validated"),
+                    cells: fixed.cells.clone(),
+                }
+            })
+            .collect();
+
+        let game = Game {
+            board: self.board.clone(),
+            pieces: copies.iter().map(|copy| self.pieces[copy.piece].clone()).collect(),
+            orientations: match self.orientations.is_empty() {
+                true => Vec::new(),
+                false => copies.iter().map(|copy| self.orientation(copy.piece).clone()).collect(),
+            },
+            piece_count: self.piece_count,
+            weights: self.weights.clone(),
+            counts: Vec::new(),
+            fixed,
+            lattice: PhantomData,
+        };
+
+        (game, copies)
+    }
+
+    pub fn orientation(&self, piece: usize) -> &Orientation {
+        const FREE: &Orientation = &Orientation::Free;
+        self.orientations.get(piece).unwrap_or(FREE)
+    }
+
+    /// Returns the permutations of every piece, respecting its orientation policy.
+    pub fn pieces_permutations(&self) -> Vec<Vec<Vec<Axial>>> {
+        (0..self.pieces.len())
+            .map(|piece| piece_permutations::<L>(self.pieces[piece].clone(), self.orientation(piece)))
+            .collect()
+    }
+
+    pub fn solver(self) -> Solver<L> {
         Solver::new(self)
     }
+
+    pub fn solver_with_options(self, options: SolverOptions) -> Solver<L> {
+        Solver::with_options(self, options)
+    }
+
+    pub fn dlx_solver(self) -> DlxSolver<L> {
+        DlxSolver::new(self)
+    }
+
+    pub fn coverage_solver(self) -> CoverageSolver<L> {
+        CoverageSolver::new(self)
+    }
 }
 
-fn piece_permutations(mut piece: Vec<Axial>) -> Vec<Vec<Axial>> {
-    canonicalize_shape(&mut piece);
-    let mut permutations = vec![piece.clone()];
+/// Returns the distinct shapes the piece takes under the transformations its orientation allows, canonicalized.
+pub fn piece_permutations<L: Lattice>(mut piece: Vec<Axial>, orientation: &Orientation) -> Vec<Vec<Axial>> {
+    canonicalize_shape::<L>(&mut piece);
+    let mut permutations: Vec<Vec<Axial>> = Vec::new();
 
-    let mut rotations = 0;
-    let mut is_flipped = false;
+    for transformation in orientation.transforms::<L>() {
+        let mut permutation = piece.clone();
+        transform::<L>(&mut permutation, transformation);
+        canonicalize_shape::<L>(&mut permutation);
 
-    #[allow(clippy::never_loop)]
-    loop {
-        'check_piece: {
-            if rotations < 6 {
-                rotate(&mut piece);
-                rotations += 1;
-                break 'check_piece;
-            }
+        if !permutations.contains(&permutation) {
+            permutations.push(permutation);
+        }
+    }
 
-            if !is_flipped {
-                flip(&mut piece);
-                rotations = 0;
-                is_flipped = true;
-                break 'check_piece;
-            }
+    permutations
+}
+
+/// Identifies the set of permutations of a piece, pieces with the same key are interchangeable.
+fn shape_key(permutations: &[Vec<Axial>]) -> Vec<Vec<u64>> {
+    let key = |piece: &Vec<Axial>| piece.iter().copied().map(Axial::key).collect::<Vec<_>>();
+    let mut keys: Vec<_> = permutations.iter().map(key).collect();
+    keys.sort();
+    keys
+}
+
+/// Groups the pieces that are interchangeable and links each to the previous piece of its group.
+///
+/// Identical pieces get the same permutations, so they enumerate their spots in the same order.
+///
+/// Fixed pieces can't be swapped with anything, so they are never grouped.
+fn group_identical(pieces_permutations: &mut [Vec<Vec<Axial>>], is_fixed: &[bool]) -> (Vec<Vec<usize>>, Vec<Option<usize>>) {
+    let mut shapes: IndexMap<Vec<Vec<u64>>, Vec<usize>> = IndexMap::new();
 
-            return permutations;
+    for (i, permutations) in pieces_permutations.iter().enumerate() {
+        if !is_fixed[i] {
+            shapes.entry(shape_key(permutations)).or_default().push(i);
         }
+    }
 
-        canonicalize_shape(&mut piece);
+    let mut previous_identical = vec![None; pieces_permutations.len()];
 
-        if !permutations.contains(&piece) {
-            permutations.push(piece.clone());
+    for pieces in shapes.values() {
+        for pair in pieces.windows(2) {
+            previous_identical[pair[1]] = Some(pair[0]);
+            pieces_permutations[pair[1]] = pieces_permutations[pair[0]].clone();
         }
     }
+
+    let identical_pieces = shapes.into_values().filter(|pieces| pieces.len() > 1).collect();
+    (identical_pieces, previous_identical)
 }
 
-#[derive(Clone)]
-pub struct Placer {
-    board: Vec<Axial>,
-    spots: Spots,
-    permutation_idx: usize,
+/// Returns every distinct ordering of `labels`, in lexicographic order.
+fn distinct_orderings(mut labels: Vec<usize>) -> Vec<Vec<usize>> {
+    labels.sort_unstable();
+    let mut orderings = vec![labels.clone()];
+
+    // step to the next ordering until the labels are descending
+    while let Some(i) = (1..labels.len()).rev().find(|&i| labels[i - 1] < labels[i]) {
+        let j = (i..labels.len()).rev().find(|&j| labels[i - 1] < labels[j]).expect("labels[i] is larger");
+        labels.swap(i - 1, j);
+        labels[i..].reverse();
+        orderings.push(labels.clone());
+    }
+
+    orderings
 }
 
-impl Default for Placer {
-    fn default() -> Self {
-        Self {
-            permutation_idx: 0,
-            board: Default::default(),
-            spots: spots(&[], &[]),
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+enum Choice {
+    /// Places a piece, `placement` indexes into the placements of the piece.
+    Place { piece: usize, placement: usize, mask: Bitboard },
+    /// Leaves a cell empty, only possible if the pieces can't cover the whole board.
+    LeaveEmpty { cell: Bitboard },
+    /// Leaves a piece of the pool unused.
+    Skip { piece: usize },
+}
+
+impl Choice {
+    fn mask(&self) -> Option<&Bitboard> {
+        match self {
+            Choice::Place { mask, .. } => Some(mask),
+            Choice::LeaveEmpty { cell } => Some(cell),
+            Choice::Skip { .. } => None,
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+enum Choices {
+    /// The placements of a single piece, starting at the cursor, followed by skipping it if it is `optional`.
+    Piece { piece: usize, cursor: usize, optional: bool },
+    /// A precomputed list, tried from back to front.
+    List(Vec<Choice>),
+}
+
+impl Default for Choices {
+    fn default() -> Self {
+        Self::List(Vec::new())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Placer {
+    board: Bitboard,
+    choices: Choices,
+    /// The piece that this placer has currently placed.
+    placed: Option<usize>,
+}
+
 impl Placer {
-    pub fn new(board: Vec<Axial>, permutations: &[Vec<Axial>]) -> Self {
-        Self {
-            spots: spots(&board, &permutations[0]),
-            board,
-            permutation_idx: 0,
-        }
+    fn new(board: Bitboard, choices: Choices) -> Self {
+        Self { board, choices, placed: None }
     }
 }
 
 impl Placer {
     #[must_use]
-    fn next_place(&mut self, placed: &mut [Axial], permutations: &[Vec<Axial>]) -> bool {
-        while let Some(piece) = permutations.get(self.permutation_idx) {
-            if let Some(spot) = self.spots.next() {
-                // we found a spot, returning...
-                placed.copy_from_slice(piece);
-                translate(placed, spot);
-                return true;
-            }
+    fn next_choice(&mut self, placements: &[Vec<Placement>]) -> Option<Choice> {
+        let (piece, cursor, optional) = match &mut self.choices {
+            Choices::Piece { piece, cursor, optional } => (*piece, cursor, *optional),
+            Choices::List(choices) => return choices.pop(),
+        };
 
-            self.permutation_idx += 1;
+        while let Some(placement) = placements[piece].get(*cursor) {
+            *cursor += 1;
 
-            if let Some(piece) = permutations.get(self.permutation_idx) {
-                self.spots = spots(&self.board, piece);
-                continue;
+            if placement.mask.is_subset(&self.board) {
+                // we found a spot, returning...
+                return Some(Choice::Place {
+                    piece,
+                    placement: *cursor - 1,
+                    mask: placement.mask.clone(),
+                });
             }
+        }
 
-            // we have exhausted all possibilities
-            break;
+        if optional && *cursor == placements[piece].len() {
+            *cursor += 1;
+            return Some(Choice::Skip { piece });
         }
 
-        false
+        // we have exhausted all possibilities
+        None
+    }
+}
+
+/// How the solver decides what to place next.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Branching {
+    /// Places the pieces one after another, trying every spot for each.
+    #[default]
+    Pieces,
+    /// Picks the free cell that the fewest placements of the remaining pieces can cover and tries each of those placements.
+    Cells,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SolverOptions {
+    /// Only reports one solution of each set of solutions that are mirror images or rotations of each other.
+    ///
+    /// The placements of the first piece are restricted to one per symmetry class, so symmetric branches are never searched.
+    pub dedup_symmetries: bool,
+    /// Reports each tiling once, instead of once for every way to swap pieces of the same shape.
+    ///
+    /// Swaps of identical pieces are never searched, this only affects the reported solutions.
+    pub distinct_tilings: bool,
+    /// Rejects a placement if it leaves a region of free cells that no combination of the remaining pieces can fill.
+    ///
+    /// Only applies if the pieces cover the whole board, or if they are a pool that has to.
+    pub prune_regions: bool,
+    pub branching: Branching,
+    /// Places the largest pieces with the fewest possible placements first, instead of following the order of the game.
+    ///
+    /// Solutions still list the pieces in the order of the game.
+    pub reorder_pieces: bool,
+    /// Only counts the solutions instead of storing them in [`Solver::solutions`].
+    pub count_only: bool,
+    /// Counts how often each piece covers each cell across all solutions, see [`Solver::coverage`].
+    pub track_coverage: bool,
+}
+
+/// Measures of how much work a search took, kept up to date by the [`Solver`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct SolverStats {
+    /// How many pieces or empty cells have been placed at each depth of the search.
+    pub nodes_per_depth: Vec<usize>,
+    /// How often a placement of each piece has been undone.
+    pub backtracks_per_piece: Vec<usize>,
+    /// Choices that fit onto the free cells and were checked by the solver.
+    pub spots_tested: usize,
+    /// Tested choices that were not rejected as symmetric, swapped or unfillable.
+    pub spots_accepted: usize,
+    /// Placements that were rejected because they left an unfillable region.
+    pub pruned_branches: usize,
+    /// Solutions reported so far, whether they are stored or not.
+    pub solutions: usize,
+    /// Time spent in [`Solver::run`] and [`Solver::solve_parallel`], stepping the iterator is not timed.
+    pub elapsed: Duration,
+    /// The largest estimated size of [`Solver::solutions`] in bytes.
+    pub peak_solutions_bytes: usize,
+}
+
+impl SolverStats {
+    /// Adds the statistics of a search over a different part of the search space.
+    pub fn merge(&mut self, other: &Self) {
+        add_counts(&mut self.nodes_per_depth, &other.nodes_per_depth);
+        add_counts(&mut self.backtracks_per_piece, &other.backtracks_per_piece);
+        self.spots_tested += other.spots_tested;
+        self.spots_accepted += other.spots_accepted;
+        self.pruned_branches += other.pruned_branches;
+        self.solutions += other.solutions;
+        self.elapsed += other.elapsed;
+        self.peak_solutions_bytes = self.peak_solutions_bytes.max(other.peak_solutions_bytes);
+    }
+}
+
+fn add_counts(counts: &mut Vec<usize>, other: &[usize]) {
+    if counts.len() < other.len() {
+        counts.resize(other.len(), 0);
     }
+
+    for (count, other) in counts.iter_mut().zip(other) {
+        *count += other;
+    }
+}
+
+/// Estimates the memory a solution takes up in [`Solver::solutions`], including its slot in the set.
+pub(crate) fn solution_bytes(solution: &Solution) -> usize {
+    let cells: usize = solution.iter().map(Vec::len).sum();
+    size_of::<Solution>() + 2 * size_of::<usize>() + size_of_val(solution.as_slice()) + cells * size_of::<Axial>()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchEvent {
+    /// A piece was placed at the given depth of the search.
+    Placed {
+        piece: usize,
+        /// The index into the permutations of the piece.
+        permutation: usize,
+        translation: Axial,
+        depth: usize,
+    },
+    /// A cell was left empty at the given depth, only when branching on cells.
+    LeftEmpty { cell: Axial, depth: usize },
+    /// A piece of the pool was left unused at the given depth, only when branching on pieces.
+    Skipped { piece: usize, depth: usize },
+    /// All choices at the current depth have been tried.
+    ///
+    /// `piece` is `None` if the depth chose between placements of several pieces.
+    Exhausted { piece: Option<usize> },
+    /// The search returned to `depth`, where the piece or empty cell is undone to try the next choice.
+    Backtracked { depth: usize },
+    /// The placed pieces were reported as a solution.
+    ///
+    /// `index` counts the solutions reported before, it indexes into [`Solver::solutions`] unless `count_only` is enabled.
+    SolutionFound { index: usize },
+    /// All solutions have been found, this is the last event.
+    Finished,
+}
+
+/// The outcome of [`Solver::solve_up_to`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveResult {
+    /// The solutions found by this search, in the order they were found.
+    pub solutions: Vec<Solution>,
+    /// Whether the search ran out of solutions and all of them have been returned.
+    pub exhausted: bool,
 }
 
 #[derive(Default, Clone)]
-pub struct Solver {
-    pub game: Game,
+pub struct Solver<L: Lattice = Hex> {
+    /// The game with the copies of its pieces listed separately.
+    pub game: Game<L>,
+    /// Which copy of which piece of the original game each piece is.
+    pub copies: Vec<PieceCopy>,
+    pub options: SolverOptions,
+    /// The symmetries of the board other than the identity, if `dedup_symmetries` is enabled.
+    pub symmetries: Vec<Symmetry>,
+    symmetry_maps: Vec<Vec<usize>>,
+    /// The piece whose placements are restricted to one per symmetry class.
+    symmetry_pivot: Option<usize>,
+    /// The symmetries that leave the placement of the pivot piece unchanged.
+    stabilizer: Vec<usize>,
+    /// Groups of at least two pieces that have the same shape.
+    pub identical_pieces: Vec<Vec<usize>>,
+    /// The previous piece of the same shape, which has to be placed at a lower mask.
+    previous_identical: Vec<Option<usize>>,
+    pub layout: BoardLayout,
     pub pieces_permutations: Vec<Vec<Vec<Axial>>>,
+    /// The placements of each piece on the empty board.
+    pub placements: Vec<Vec<Placement>>,
+    /// The order in which the pieces are placed when branching on pieces.
+    pub order: Vec<usize>,
     pub placers: Vec<Placer>,
     pub pieces: Vec<Vec<Axial>>,
-    pub solutions: IndexSet<Vec<Vec<Axial>>>,
+    pub masks: Vec<Bitboard>,
+    pub is_placed: Vec<bool>,
+    /// The pieces that are placed by [`Game::fixed`].
+    pub is_fixed: Vec<bool>,
+    pub solutions: IndexSet<Solution>,
+    /// For every piece, how many solutions it covers each cell of the board in, if `track_coverage` is enabled.
+    ///
+    /// Cells are in the order of [`Game::board`].
+    pub coverage: Vec<Vec<usize>>,
+    /// Maps the bit indices of the layout to the indices of the board cells.
+    board_indices: Vec<usize>,
     pub work_idx: usize,
+    /// Whether the whole search space has been searched.
+    finished: bool,
+    /// Events that happened but have not been yielded by the iterator yet.
+    events: VecDeque<SearchEvent>,
+    /// How many of the stored solutions [`Solver::solve_up_to`] has returned.
+    returned: usize,
+    /// The estimated size of the stored solutions, see [`SolverStats::peak_solutions_bytes`].
+    solutions_bytes: usize,
+    pub stats: SolverStats,
+    neighbours: Vec<Bitboard>,
 }
 
-impl Debug for Solver {
+impl<L: Lattice> Debug for Solver<L> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Solver")
             .field("game", &self.game)
+            .field("options", &self.options)
             .field("solutions", &self.solutions)
             .field("work_idx", &self.work_idx)
+            .field("stats", &self.stats)
             .finish()
     }
 }
 
-impl Solver {
-    pub fn new(game: Game) -> Self {
-        let pieces_permutations: Vec<_> = game.pieces.iter().cloned().map(piece_permutations).collect();
-        let mut placers = vec![Default::default(); game.pieces.len()];
+impl<L: Lattice> Solver<L> {
+    /// Panics if the game is invalid, see [`Game::validate`].
+    pub fn new(game: Game<L>) -> Self {
+        Self::with_options(game, Default::default())
+    }
+
+    /// Panics if the game is invalid, see [`Game::validate`].
+    pub fn with_options(game: Game<L>, options: SolverOptions) -> Self {
+        Self::try_with_options(game, options).unwrap_or_else(|error| panic!("invalid game: {error}"))
+    }
+
+    pub fn try_new(game: Game<L>) -> Result<Self, GameError> {
+        Self::try_with_options(game, Default::default())
+    }
+
+    pub fn try_with_options(game: Game<L>, options: SolverOptions) -> Result<Self, GameError> {
+        Self::try_with_permutations(game, options, Game::pieces_permutations)
+    }
 
-        if !game.pieces.is_empty() {
-            placers[0] = Placer::new(game.board.clone(), &pieces_permutations[0]);
+    /// Like [`Self::try_with_options`], but the permutations of the pieces of the expanded game come from `permutations`.
+    pub(crate) fn try_with_permutations(
+        game: Game<L>,
+        options: SolverOptions,
+        permutations: impl FnOnce(&Game<L>) -> Vec<Vec<Vec<Axial>>>,
+    ) -> Result<Self, GameError> {
+        game.validate()?;
+
+        let (game, copies) = game.expand_copies();
+        let layout = BoardLayout::new(&game.board);
+        let mut board = layout.mask(&game.board).expect("board fits into its own layout");
+        let mut pieces = game.pieces.clone();
+        let mut masks = vec![Bitboard::ZERO; game.pieces.len()];
+        let mut is_fixed = vec![false; game.pieces.len()];
+
+        for fixed in &game.fixed {
+            let mask = layout.mask(&fixed.cells).expect("fixed pieces are on the board");
+            place(&mut board, &mask);
+
+            pieces[fixed.piece] = fixed.cells.clone();
+            canonicalize_place(&mut pieces[fixed.piece]);
+            masks[fixed.piece] = mask;
+            is_fixed[fixed.piece] = true;
         }
 
-        Self {
+        let mut pieces_permutations = permutations(&game);
+        let (identical_pieces, previous_identical) = group_identical(&mut pieces_permutations, &is_fixed);
+        let placements = placement::placement_table::<L>(&game.board, &pieces_permutations);
+
+        // fixed pieces are never placed by the search
+        let mut order: Vec<usize> = (0..game.pieces.len()).filter(|&piece| !is_fixed[piece]).collect();
+
+        if options.reorder_pieces {
+            // the sort is stable, so identical pieces stay in order
+            order.sort_by_key(|&piece| (Reverse(game.pieces[piece].len()), placements[piece].len()));
+        }
+
+        let mut symmetries = Vec::new();
+
+        if options.dedup_symmetries {
+            symmetries = board_symmetries::<L>(&game.board);
+            symmetries.retain(|symmetry| symmetry.transform != Transform::IDENTITY);
+            // a symmetric image of a solution is only a solution if every piece may take the transformed orientations
+            symmetries.retain(|symmetry| {
+                pieces_permutations.iter().all(|permutations| {
+                    permutations.iter().all(|permutation| {
+                        let mut image = permutation.clone();
+                        transform::<L>(&mut image, symmetry.transform);
+                        canonicalize_shape::<L>(&mut image);
+                        permutations.contains(&image)
+                    })
+                })
+            });
+            // and only if it leaves the fixed pieces where they are
+            symmetries.retain(|symmetry| {
+                game.fixed.iter().all(|fixed| {
+                    let mut image: Vec<Axial> = fixed.cells.iter().map(|&cell| symmetry.apply::<L>(cell)).collect();
+                    let mut cells = fixed.cells.clone();
+                    canonicalize_place(&mut image);
+                    canonicalize_place(&mut cells);
+                    image == cells
+                })
+            });
+        }
+
+        // restricting a piece that has identical twins would conflict with the order we impose on them
+        let symmetry_pivot = order.iter().copied().find(|&i| identical_pieces.iter().all(|pieces| !pieces.contains(&i)));
+        let stabilizer = match symmetry_pivot {
+            Some(_) => Vec::new(),
+            None => (0..symmetries.len()).collect(),
+        };
+
+        let mut board_indices = vec![usize::MAX; layout.len()];
+
+        for (i, &cell) in game.board.iter().enumerate() {
+            board_indices[layout.index(cell).expect("board fits into its own layout")] = i;
+        }
+
+        let pieces_area: usize = game.pieces.iter().map(Vec::len).sum();
+        let is_exact = pieces_area == game.board.len();
+
+        let mut this = Self {
+            options,
+            neighbours: if options.prune_regions && (is_exact || game.piece_count.is_pool()) {
+                layout.neighbours::<L>()
+            } else {
+                Vec::new()
+            },
+            stats: SolverStats {
+                backtracks_per_piece: vec![0; game.pieces.len()],
+                ..Default::default()
+            },
+            symmetry_maps: symmetries.iter().map(|symmetry| symmetry.index_map::<L>(layout, &game.board)).collect(),
+            symmetries,
+            symmetry_pivot,
+            stabilizer,
+            identical_pieces,
+            previous_identical,
+            layout,
             pieces_permutations,
-            placers,
-            pieces: game.pieces.clone(),
+            placements,
+            order,
+            placers: Vec::new(),
+            pieces,
+            masks,
+            is_placed: is_fixed.clone(),
+            is_fixed,
             solutions: Default::default(),
+            coverage: if options.track_coverage {
+                vec![vec![0; game.board.len()]; game.pieces.len()]
+            } else {
+                Vec::new()
+            },
+            board_indices,
             work_idx: 0,
+            finished: false,
+            events: VecDeque::new(),
+            returned: 0,
+            solutions_bytes: 0,
+            copies,
             game,
+        };
+
+        if !this.is_complete(&board) {
+            this.placers.push(this.next_placer(board));
         }
+
+        Ok(this)
+    }
+
+    /// Returns whether all solutions have been found.
+    pub fn is_finished(&self) -> bool {
+        self.finished
     }
-}
 
-impl Iterator for Solver {
-    type Item = ();
+    /// Returns the next `n` solutions, searching until they have been found or there are no more.
+    ///
+    /// A tiling is reported once per labelling of identical pieces, so the search can find more than `n`.
+    /// The rest are returned by the next call.
+    pub fn solve_up_to(&mut self, n: usize) -> SolveResult {
+        let target = self.stats.solutions.saturating_add(n);
 
-    fn next(&mut self) -> Option<()> {
         loop {
-            if let Some(placer) = self.placers.get_mut(self.work_idx) {
-                let placed_piece = &mut self.pieces[self.work_idx];
-                let piece_permutations = &self.pieces_permutations[self.work_idx];
+            let found = match self.options.count_only {
+                // nothing is stored, so only the reported solutions can be counted
+                true => self.stats.solutions >= target,
+                false => self.solutions.len() - self.returned >= n,
+            };
 
-                if placer.next_place(placed_piece, piece_permutations) {
-                    // we placed our piece, lets move on to the next one
-                    self.work_idx += 1;
+            if found || self.next().is_none() {
+                break;
+            }
+        }
+
+        let solutions: Vec<Solution> = self.solutions.iter().skip(self.returned).take(n).cloned().collect();
+        self.returned += solutions.len();
+
+        SolveResult {
+            solutions,
+            exhausted: self.finished && self.returned == self.solutions.len(),
+        }
+    }
+
+    /// Returns the next solution that [`Solver::solve_up_to`] has not returned yet.
+    ///
+    /// Always returns `None` if `count_only` is enabled, as solutions are not stored then.
+    pub fn next_solution(&mut self) -> Option<Solution> {
+        self.solve_up_to(1).solutions.pop()
+    }
+
+    /// Searches until a solution has been found, unless one has been found already.
+    pub fn is_solvable(&mut self) -> bool {
+        if self.stats.solutions == 0 {
+            self.solve_up_to(1);
+        }
+
+        self.stats.solutions > 0
+    }
+
+    /// Returns the pieces that are currently placed, in the order they were placed in.
+    pub fn placed(&self) -> impl Iterator<Item = usize> + '_ {
+        self.placers[..self.work_idx].iter().filter_map(|placer| placer.placed)
+    }
+
+    /// Returns whether nothing is left to place on the board.
+    fn is_complete(&self, board: &Bitboard) -> bool {
+        if self.game.piece_count.is_pool() {
+            board.is_zero()
+        } else {
+            self.is_placed.iter().all(|&is_placed| is_placed)
+        }
+    }
+
+    /// Counts the pieces that have been placed rather than skipped.
+    fn used_count(&self) -> usize {
+        (0..self.game.pieces.len())
+            .filter(|&piece| self.is_placed[piece] && !self.masks[piece].is_zero())
+            .count()
+    }
+
+    fn unplaced(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.game.pieces.len()).filter(|&piece| !self.is_placed[piece])
+    }
+
+    fn next_placer(&self, board: Bitboard) -> Placer {
+        let choices = match self.options.branching {
+            Branching::Pieces => match self.order.get(self.work_idx) {
+                Some(&piece) => Choices::Piece {
+                    piece,
+                    cursor: 0,
+                    optional: self.game.piece_count.is_pool(),
+                },
+                // the pool is used up without covering the board
+                None => Choices::List(Vec::new()),
+            },
+            Branching::Cells => Choices::List(self.cell_choices(&board)),
+        };
+
+        Placer::new(board, choices)
+    }
+
+    /// Returns the placements that cover the free cell with the fewest such placements.
+    fn cell_choices(&self, board: &Bitboard) -> Vec<Choice> {
+        let mut coverage = vec![0; self.layout.len()];
+        let mut places = Vec::new();
+
+        for piece in self.unplaced() {
+            if self.previous_identical[piece].is_some_and(|previous| !self.is_placed[previous]) {
+                // identical pieces are placed in order
+                continue;
+            }
+
+            for (placement, Placement { mask, .. }) in self.placements[piece].iter().enumerate() {
+                if !mask.is_subset(board) {
+                    continue;
+                }
+
+                for index in mask.ones() {
+                    coverage[index] += 1;
+                }
+
+                places.push(Choice::Place {
+                    piece,
+                    placement,
+                    mask: mask.clone(),
+                });
+            }
+        }
+
+        let Some(cell) = board.ones().min_by_key(|&index| coverage[index]) else {
+            return Vec::new();
+        };
+
+        let mut choices: Vec<Choice> = places
+            .into_iter()
+            .filter(|choice| choice.mask().is_some_and(|mask| mask.contains(cell)))
+            .collect();
+        let unplaced_area: usize = self.unplaced().map(|piece| self.game.pieces[piece].len()).sum();
+
+        if board.count() > unplaced_area && !self.game.piece_count.is_pool() {
+            choices.push(Choice::LeaveEmpty { cell: Bitboard::bit(cell) });
+        }
+
+        choices.reverse();
+        choices
+    }
 
-                    if self.work_idx < self.game.pieces.len() {
-                        let mut board = placer.board.clone();
-                        place(&mut board, placed_piece);
+    fn accepts(&mut self, board: &Bitboard, choice: &Choice) -> bool {
+        if let PieceCount::Exactly(count) = self.game.piece_count {
+            match choice {
+                Choice::Place { .. } if self.used_count() == count => return false,
+                // the pieces after this one would not be enough
+                Choice::Skip { .. } if self.used_count() + self.order.len() - self.work_idx - 1 < count => return false,
+                _ => {}
+            }
+        }
 
-                        self.placers[self.work_idx] = Placer::new(board, &self.pieces_permutations[self.work_idx]);
+        if let &Choice::Place { piece, ref mask, .. } = choice {
+            if self.symmetry_pivot == Some(piece) && !Self::is_canonical_pivot_place(&self.symmetry_maps, &mut self.stabilizer, mask) {
+                // a symmetric placement has been or will be searched instead
+                return false;
+            }
+
+            if self.options.branching == Branching::Pieces {
+                if let Some(previous) = self.previous_identical[piece] {
+                    if *mask <= self.masks[previous] {
+                        // this is just a swap of identical pieces
+                        return false;
                     }
 
-                    // we did something :) lets return
-                    break Some(());
+                    if self.masks[previous].is_zero() {
+                        // the unused pieces of a pool come last among identical pieces
+                        return false;
+                    }
                 }
+            }
 
-                // we failed to place our work piece
+            let mut board = board.clone();
+            place(&mut board, mask);
+
+            if !self.is_fillable(&board, piece) {
+                self.stats.pruned_branches += 1;
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Returns false if a symmetric image of this placement of the pivot piece comes before it.
+    fn is_canonical_pivot_place(symmetry_maps: &[Vec<usize>], stabilizer: &mut Vec<usize>, mask: &Bitboard) -> bool {
+        stabilizer.clear();
+
+        for (i, map) in symmetry_maps.iter().enumerate() {
+            let mapped = symmetry::map_mask(map, mask);
+
+            if &mapped < mask {
+                return false;
+            }
+
+            if &mapped == mask {
+                stabilizer.push(i);
+            }
+        }
+
+        true
+    }
+
+    /// Returns false if a symmetric image of the current solution comes before it.
+    ///
+    /// Only the symmetries that leave the pivot piece in place need to be checked,
+    /// any other image has already been skipped when placing the pivot piece.
+    /// If the pivot piece is left unused, every symmetry is checked.
+    fn is_canonical_solution(&self, masks: &[Bitboard]) -> bool {
+        let all: Vec<usize>;
+        let symmetries = match self.symmetry_pivot {
+            Some(pivot) if masks[pivot].is_zero() => {
+                all = (0..self.symmetry_maps.len()).collect();
+                &all
+            }
+            _ => &self.stabilizer,
+        };
+
+        symmetries.iter().all(|&i| {
+            let map = &self.symmetry_maps[i];
+            let mut mapped: Vec<Bitboard> = masks.iter().map(|mask| symmetry::map_mask(map, mask)).collect();
+            self.sort_identical(&mut mapped, &mut []);
+            mapped.as_slice() >= masks
+        })
+    }
+
+    /// Reorders the placements of identical pieces by their masks, so every tiling has one labelling.
+    ///
+    /// The cells of the pieces are reordered too, unless `pieces` is empty.
+    fn sort_identical(&self, masks: &mut [Bitboard], pieces: &mut [Vec<Axial>]) {
+        for identical in &self.identical_pieces {
+            let mut sorted = identical.clone();
+            // unused pieces of a pool go last
+            sorted.sort_unstable_by_key(|&piece| (masks[piece].is_zero(), masks[piece].clone()));
+
+            let sorted_masks: Vec<Bitboard> = sorted.iter().map(|&piece| masks[piece].clone()).collect();
+            let sorted_pieces: Vec<Vec<Axial>> = sorted.iter().filter_map(|&piece| pieces.get(piece).cloned()).collect();
+
+            for (i, &piece) in identical.iter().enumerate() {
+                masks[piece].clone_from(&sorted_masks[i]);
+
+                if let Some(cells) = sorted_pieces.get(i) {
+                    pieces[piece].clone_from(cells);
+                }
+            }
+        }
+    }
+
+    /// Returns false if the free cells split into a region that can't be filled by the unplaced pieces other than `piece`.
+    fn is_fillable(&self, board: &Bitboard, piece: usize) -> bool {
+        if self.neighbours.is_empty() {
+            return true;
+        }
+
+        let mut areas = Bitboard::bit(0);
+
+        for other in self.unplaced().filter(|&other| other != piece) {
+            areas |= &areas << self.game.pieces[other].len();
+        }
+
+        self.layout
+            .regions(&self.neighbours, board.clone())
+            .all(|region| areas.contains(region.count()))
+    }
+
+    /// Searches until something is placed or the search is finished, queueing the events on the way.
+    fn step(&mut self) {
+        loop {
+            if self.work_idx == self.placers.len() {
+                // we placed all pieces, so we got a solution
+                if let Some(index) = self.record_solution() {
+                    self.events.push_back(SearchEvent::SolutionFound { index });
+                }
 
                 if self.work_idx == 0 {
+                    // there was nothing to place
+                    self.finish();
+                    return;
+                }
+
+                // lets try to find more solutions
+                self.backtrack();
+                continue;
+            }
+
+            let depth = self.work_idx;
+
+            if let Some(piece) = self.placers[depth].placed.take() {
+                self.is_placed[piece] = false;
+                self.stats.backtracks_per_piece[piece] += 1;
+            }
+
+            let board = self.placers[depth].board.clone();
+            let mut choice = None;
+
+            while let Some(next) = self.placers[depth].next_choice(&self.placements) {
+                self.stats.spots_tested += 1;
+
+                if self.accepts(&board, &next) {
+                    self.stats.spots_accepted += 1;
+                    choice = Some(next);
+                    break;
+                }
+            }
+
+            let Some(choice) = choice else {
+                // we failed to place our work piece
+                let piece = match self.placers[depth].choices {
+                    Choices::Piece { piece, .. } => Some(piece),
+                    Choices::List(_) => None,
+                };
+                self.events.push_back(SearchEvent::Exhausted { piece });
+
+                if depth == 0 {
                     // we exhausted all transformations, all solutions have been found
-                    break None;
+                    self.finish();
+                    return;
                 }
 
                 // ugh, lets try something else
-                self.work_idx -= 1;
+                self.backtrack();
                 continue;
+            };
+
+            match &choice {
+                &Choice::Place { piece, placement, ref mask } => {
+                    // we placed our piece, lets move on to the next one
+                    let placement = &self.placements[piece][placement];
+                    self.pieces[piece].clone_from(&placement.cells);
+
+                    self.masks[piece].clone_from(mask);
+                    self.is_placed[piece] = true;
+                    self.placers[depth].placed = Some(piece);
+
+                    self.events.push_back(SearchEvent::Placed {
+                        piece,
+                        permutation: placement.permutation,
+                        translation: placement.translation,
+                        depth,
+                    });
+                }
+                Choice::LeaveEmpty { cell } => {
+                    let cell = self.layout.cells(cell).next().expect("a single cell is left empty");
+                    self.events.push_back(SearchEvent::LeftEmpty { cell, depth });
+                }
+                &Choice::Skip { piece } => {
+                    self.pieces[piece].clear();
+                    self.masks[piece] = Bitboard::ZERO;
+                    self.is_placed[piece] = true;
+                    self.placers[depth].placed = Some(piece);
+
+                    self.events.push_back(SearchEvent::Skipped { piece, depth });
+                }
+            }
+
+            if self.stats.nodes_per_depth.len() <= depth {
+                self.stats.nodes_per_depth.resize(depth + 1, 0);
+            }
+
+            self.stats.nodes_per_depth[depth] += 1;
+            self.work_idx += 1;
+            self.placers.truncate(self.work_idx);
+
+            let mut board = board;
+
+            if let Some(mask) = choice.mask() {
+                place(&mut board, mask);
+            }
+
+            if !self.is_complete(&board) {
+                self.placers.push(self.next_placer(board));
             }
 
-            // we exhausted all placers, so we got a solution
-            self.solutions.insert({
-                let mut pieces = self.pieces.clone();
-                for piece in &mut pieces {
-                    canonicalize_place(piece);
+            // we did something :) lets return
+            return;
+        }
+    }
+
+    fn backtrack(&mut self) {
+        self.work_idx -= 1;
+        self.events.push_back(SearchEvent::Backtracked { depth: self.work_idx });
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+        self.events.push_back(SearchEvent::Finished);
+    }
+
+    /// Returns the index of the first reported solution, if the current placements were reported at all.
+    fn record_solution(&mut self) -> Option<usize> {
+        if let PieceCount::Exactly(count) = self.game.piece_count {
+            if self.used_count() != count {
+                return None;
+            }
+        }
+
+        // pieces of a pool that are left unused have no cells
+        let mut masks: Vec<Bitboard> = (0..self.game.pieces.len())
+            .map(|piece| if self.is_placed[piece] { self.masks[piece].clone() } else { Bitboard::ZERO })
+            .collect();
+        let mut pieces: Vec<Vec<Axial>> = (0..self.game.pieces.len())
+            .map(|piece| if self.is_placed[piece] { self.pieces[piece].clone() } else { Vec::new() })
+            .collect();
+        self.sort_identical(&mut masks, &mut pieces);
+
+        if !self.is_canonical_solution(&masks) {
+            return None;
+        }
+
+        let index = self.stats.solutions;
+        let labelled_masks = self.labellings(masks);
+        self.stats.solutions += labelled_masks.len();
+
+        if !self.coverage.is_empty() {
+            for masks in &labelled_masks {
+                for (coverage, mask) in self.coverage.iter_mut().zip(masks) {
+                    for index in mask.ones() {
+                        coverage[self.board_indices[index]] += 1;
+                    }
                 }
-                pieces
-            });
+            }
+        }
+
+        if !self.options.count_only {
+            for piece in &mut pieces {
+                canonicalize_place(piece);
+            }
 
-            // lets try to find more solutions
-            self.work_idx -= 1;
-            continue;
+            for solution in self.labellings(pieces) {
+                let bytes = solution_bytes(&solution);
+
+                if self.solutions.insert(solution) {
+                    self.solutions_bytes += bytes;
+                }
+            }
+
+            self.stats.peak_solutions_bytes = self.stats.peak_solutions_bytes.max(self.solutions_bytes);
         }
+
+        Some(index)
+    }
+
+    /// Returns the solution once for every distinct way to assign the cells of identical pieces.
+    ///
+    /// Copies of the same piece are interchangeable, so only cells of different pieces are swapped.
+    fn labellings<T: Clone + Eq + Hash>(&self, solution: Vec<T>) -> Vec<Vec<T>> {
+        let mut labellings = vec![solution];
+
+        if self.options.distinct_tilings {
+            return labellings;
+        }
+
+        for pieces in &self.identical_pieces {
+            let sources: Vec<usize> = pieces.iter().map(|&piece| self.copies[piece].piece).collect();
+
+            if sources.iter().all(|&source| source == sources[0]) {
+                // the copies of a single piece have one labelling
+                continue;
+            }
+
+            labellings = labellings
+                .into_iter()
+                .flat_map(|solution| {
+                    distinct_orderings(sources.clone()).into_iter().map(move |owners| {
+                        let mut labelled = solution.clone();
+                        let mut targets = pieces.clone();
+
+                        // the copies of each piece take its cells in order
+                        for (&piece, owner) in pieces.iter().zip(owners) {
+                            let target = targets
+                                .iter()
+                                .position(|&target| self.copies[target].piece == owner)
+                                .expect("a copy of the owner is left");
+                            labelled[targets.remove(target)] = solution[piece].clone();
+                        }
+
+                        labelled
+                    })
+                })
+                // unused identical pieces are indistinguishable
+                .collect::<IndexSet<_>>()
+                .into_iter()
+                .collect();
+        }
+
+        labellings
+    }
+}
+
+impl<L: Lattice> Iterator for Solver<L> {
+    type Item = SearchEvent;
+
+    fn next(&mut self) -> Option<SearchEvent> {
+        if self.events.is_empty() && !self.finished {
+            self.step();
+        }
+
+        self.events.pop_front()
     }
 }
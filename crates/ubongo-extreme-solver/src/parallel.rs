@@ -0,0 +1,127 @@
+use crate::{solution_bytes, Choices, Lattice, Solution, Solver, SolverStats};
+use indexmap::IndexSet;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+#[derive(Clone)]
+struct Branch {
+    solutions: IndexSet<Solution>,
+    coverage: Vec<Vec<usize>>,
+    stats: SolverStats,
+}
+
+impl<L: Lattice> Solver<L> {
+    /// Finds all remaining solutions using up to `threads` threads.
+    ///
+    /// Every choice for the first placement becomes a branch that is searched on its own,
+    /// and so does the rest of the branch the search is currently in.
+    /// The solutions are merged in the order the branches would be searched in sequentially,
+    /// so the result is the same as exhausting the iterator.
+    pub fn solve_parallel(&mut self, threads: usize) {
+        let start = Instant::now();
+
+        let Some(root) = self.placers.first_mut() else {
+            self.for_each(drop);
+            return;
+        };
+
+        // the current branch is finished by a clone of this solver, the other choices become branches
+        let mut remaining = root.clone();
+        remaining.choices = std::mem::take(&mut root.choices);
+        let mut branches = Vec::new();
+
+        while let Some(choice) = remaining.next_choice(&self.placements) {
+            branches.push(choice);
+        }
+
+        let mut template = self.clone();
+        template.work_idx = 0;
+        template.placers.truncate(1);
+        template.placers[0].placed = None;
+        template.is_placed.clone_from(&self.is_fixed);
+        template.events.clear();
+        template.solutions.clear();
+        template.coverage.iter_mut().for_each(|coverage| coverage.fill(0));
+        template.solutions_bytes = 0;
+        template.stats = SolverStats {
+            backtracks_per_piece: vec![0; self.game.pieces.len()],
+            ..Default::default()
+        };
+
+        let current = Mutex::new(Some(self.clone()));
+        let next_branch = AtomicUsize::new(0);
+        let mut results: Vec<Option<Branch>> = vec![None; branches.len()];
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.clamp(1, branches.len() + 1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut finished = Vec::new();
+                        let mut finished_current = None;
+
+                        loop {
+                            let i = next_branch.fetch_add(1, Ordering::Relaxed);
+
+                            if i == 0 {
+                                let mut solver: Self = current.lock().expect("no worker panicked").take().expect("taken once");
+                                solver.by_ref().for_each(drop);
+                                finished_current = Some(solver);
+                                continue;
+                            }
+
+                            let Some(choice) = branches.get(i - 1).cloned() else {
+                                break (finished, finished_current);
+                            };
+
+                            let mut branch = template.clone();
+                            branch.placers[0].choices = Choices::List(vec![choice]);
+                            branch.by_ref().for_each(drop);
+                            finished.push((
+                                i - 1,
+                                Branch {
+                                    solutions: branch.solutions,
+                                    coverage: branch.coverage,
+                                    stats: branch.stats,
+                                },
+                            ));
+                        }
+                    })
+                })
+                .collect();
+
+            for worker in workers {
+                let (finished, finished_current) = worker.join().expect("solver thread panicked");
+
+                for (i, branch) in finished {
+                    results[i] = Some(branch);
+                }
+
+                if let Some(solver) = finished_current {
+                    // the current branch comes first, as it would sequentially
+                    *self = solver;
+                }
+            }
+        });
+
+        for branch in results.into_iter().flatten() {
+            self.solutions.extend(branch.solutions);
+            self.stats.merge(&branch.stats);
+
+            for (coverage, branch_coverage) in self.coverage.iter_mut().zip(branch.coverage) {
+                for (count, branch_count) in coverage.iter_mut().zip(branch_coverage) {
+                    *count += branch_count;
+                }
+            }
+        }
+
+        self.solutions_bytes = self.solutions.iter().map(solution_bytes).sum();
+        self.stats.peak_solutions_bytes = self.stats.peak_solutions_bytes.max(self.solutions_bytes);
+        self.stats.elapsed += start.elapsed();
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{translate, Axial, Bitboard, BoardLayout, Game, Lattice, Spots};
+use serde::{Deserialize, Serialize};
+
+/// A legal position of a piece on the board.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Placement {
+    /// The index of the piece in [`Game::pieces`].
+    pub piece: usize,
+    /// The index of the permutation in the permutations of the piece.
+    pub permutation: usize,
+    pub translation: Axial,
+    pub cells: Vec<Axial>,
+    /// The cells as a mask of the [`BoardLayout`] of the board.
+    pub mask: Bitboard,
+}
+
+/// Returns every placement of every permutation of every piece on the board of the game.
+pub fn placements<L: Lattice>(game: &Game<L>) -> Vec<Placement> {
+    placement_table::<L>(&game.board, &game.pieces_permutations()).into_iter().flatten().collect()
+}
+
+/// Returns the placements of each piece, ordered by permutation and then by translation.
+pub(crate) fn placement_table<L: Lattice>(board: &[Axial], pieces_permutations: &[Vec<Vec<Axial>>]) -> Vec<Vec<Placement>> {
+    let layout = BoardLayout::new(board);
+    let board = layout.mask(board).expect("board fits into its own layout");
+
+    pieces_permutations
+        .iter()
+        .enumerate()
+        .map(|(piece, permutations)| {
+            let mut placements = Vec::new();
+
+            for (permutation, cells) in permutations.iter().enumerate() {
+                for (translation, mask) in Spots::<L>::new(layout, board.clone(), cells) {
+                    let mut cells = cells.clone();
+                    translate(&mut cells, translation);
+
+                    placements.push(Placement {
+                        piece,
+                        permutation,
+                        translation,
+                        cells,
+                        mask,
+                    });
+                }
+            }
+
+            placements
+        })
+        .collect()
+}
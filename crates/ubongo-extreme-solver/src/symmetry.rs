@@ -0,0 +1,119 @@
+use crate::{canonicalize_shape, min, Axial, Bitboard, BoardLayout, Lattice};
+use serde::{Deserialize, Serialize};
+
+/// An element of the symmetry group of a [`Lattice`], 12 on the hexagonal one.
+///
+/// Rotates by `rotation` steps around the origin and then mirrors the result if `flip` is set.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transform {
+    pub rotation: u8,
+    pub flip: bool,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self { rotation: 0, flip: false };
+
+    pub fn all<L: Lattice>() -> impl Iterator<Item = Self> {
+        [false, true]
+            .into_iter()
+            .flat_map(|flip| (0..L::ROTATIONS).map(move |rotation| Self { rotation, flip }))
+    }
+
+    #[must_use]
+    pub fn apply<L: Lattice>(self, coord: Axial) -> Axial {
+        let rotated = L::rotate_many(coord, self.rotation as usize);
+        if self.flip {
+            L::flip(rotated)
+        } else {
+            rotated
+        }
+    }
+}
+
+/// Which orientations a piece may be placed in.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    /// Every rotation of the piece and of its mirror image.
+    #[default]
+    Free,
+    /// Every rotation, but the piece can't be flipped.
+    RotateOnly,
+    /// The piece as given or mirrored, but not rotated.
+    FlipOnly,
+    /// Only the listed transformations of the piece as given.
+    Transforms(Vec<Transform>),
+}
+
+impl Orientation {
+    /// Returns the allowed transformations, the rotations of the mirror image are in the order they are reached by rotating it.
+    pub fn transforms<L: Lattice>(&self) -> Vec<Transform> {
+        let rotations = (0..L::ROTATIONS).map(|rotation| Transform { rotation, flip: false });
+        // rotating the mirror image by `k` is the same as mirroring the piece rotated by `-k`
+        let flipped_rotations = (0..L::ROTATIONS).map(|k| Transform {
+            rotation: (L::ROTATIONS - k) % L::ROTATIONS,
+            flip: true,
+        });
+
+        match self {
+            Self::Free => rotations.chain(flipped_rotations).collect(),
+            Self::RotateOnly => rotations.collect(),
+            Self::FlipOnly => vec![Transform::IDENTITY, Transform { rotation: 0, flip: true }],
+            Self::Transforms(transforms) => transforms.clone(),
+        }
+    }
+}
+
+/// A transformation that maps a board onto itself.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    pub transform: Transform,
+    pub offset: Axial,
+}
+
+impl Symmetry {
+    #[must_use]
+    pub fn apply<L: Lattice>(self, coord: Axial) -> Axial {
+        self.transform.apply::<L>(coord) + self.offset
+    }
+
+    /// Maps every bit index of the board to the bit index of its image.
+    ///
+    /// Indices outside of the board map to themselves.
+    pub fn index_map<L: Lattice>(self, layout: BoardLayout, board: &[Axial]) -> Vec<usize> {
+        let mut map: Vec<usize> = (0..layout.len()).collect();
+
+        for &coord in board {
+            let from = layout.index(coord).expect("board fits into its own layout");
+            map[from] = layout.index(self.apply::<L>(coord)).expect("symmetries map the board onto itself");
+        }
+
+        map
+    }
+}
+
+/// Returns all transformations that map the board onto itself, including the identity.
+pub fn board_symmetries<L: Lattice>(board: &[Axial]) -> Vec<Symmetry> {
+    let mut canonical_board = board.to_vec();
+    canonicalize_shape::<L>(&mut canonical_board);
+
+    Transform::all::<L>()
+        .filter_map(|transform| {
+            let mut transformed: Vec<Axial> = board.iter().map(|&coord| transform.apply::<L>(coord)).collect();
+            let offset = min(board) - min(&transformed);
+            canonicalize_shape::<L>(&mut transformed);
+
+            (transformed == canonical_board).then_some(Symmetry { transform, offset })
+        })
+        .collect()
+}
+
+pub(crate) fn map_mask(map: &[usize], mask: &Bitboard) -> Bitboard {
+    let mut mapped = Bitboard::ZERO;
+
+    for index in mask.ones() {
+        mapped.set(map[index]);
+    }
+
+    mapped
+}
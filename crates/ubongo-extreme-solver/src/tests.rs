@@ -1,4 +1,8 @@
-use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    time::Duration,
+};
 
 use expect_test::expect;
 
@@ -92,25 +96,38 @@ fn canonical_debug() {
 
 const MAX_STEPS: usize = 1_000_000;
 
-fn test_file(name: &str) -> String {
+const TEST_FILES: [&str; 6] = ["b4", "b18b", "b18g", "b18r", "b18y", "b38y"];
+
+fn test_game(name: &str) -> Game {
+    lattice_game(name)
+}
+
+fn lattice_game<L: Lattice>(name: &str) -> Game<L> {
     let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("data/{name}.json"));
     let content = std::fs::read_to_string(path).unwrap();
-    let game: Game = serde_json::from_str(&content).unwrap();
-    let mut solver = game.solver();
-    let step_count = (&mut solver).take(MAX_STEPS).count();
+    serde_json::from_str(&content).unwrap()
+}
 
-    if step_count >= MAX_STEPS {
-        panic!("steps count exceeded {MAX_STEPS}");
-    }
+fn test_file(name: &str) -> String {
+    let mut solver = test_game(name).solver();
+    let result = solver.run(&Budget {
+        max_steps: Some(MAX_STEPS),
+        ..Default::default()
+    });
 
-    println!("this took {step_count} steps");
+    assert_eq!(result.outcome, RunOutcome::Finished, "steps count exceeded {MAX_STEPS}");
+    println!("this took {} steps", result.steps);
     serde_json::to_string(&solver.solutions).unwrap()
 }
 
 fn test_perms(len: usize, piece: Vec<Axial>) {
+    test_oriented_perms(len, piece, &Orientation::Free);
+}
+
+fn test_oriented_perms(len: usize, piece: Vec<Axial>, orientation: &Orientation) {
     println!("===============================================================");
-    println!("    {piece:?}");
-    let permutations = piece_permutations(piece);
+    println!("    {piece:?} {orientation:?}");
+    let permutations = piece_permutations::<Hex>(piece, orientation);
 
     for (i, perm) in permutations.iter().enumerate() {
         println!("{i:2}: {perm:?}");
@@ -126,14 +143,20 @@ fn permutations() {
     test_perms(3, vec![Axial(0, 0), Axial(1, 0), Axial(2, 0)]);
     test_perms(3, vec![Axial(0, 0), Axial(1, 0)]);
     test_perms(1, vec![Axial(0, 0)]);
+
+    let l = vec![Axial(0, 0), Axial(1, 0), Axial(2, 0), Axial(2, 1)];
+    test_oriented_perms(6, l.clone(), &Orientation::RotateOnly);
+    test_oriented_perms(2, l.clone(), &Orientation::FlipOnly);
+    test_oriented_perms(1, l.clone(), &Orientation::Transforms(vec![Transform::IDENTITY]));
+    test_oriented_perms(3, vec![Axial(0, 0), Axial(1, 0)], &Orientation::RotateOnly);
 }
 
 #[test]
 fn canon_simple() {
     let mut a = vec![Axial(0, 0), Axial(1, 0), Axial(0, 1), Axial(1, 1)];
     let mut b = vec![Axial(0, 1), Axial(1, 1), Axial(0, 0), Axial(1, 0)];
-    canonicalize_shape(&mut a);
-    canonicalize_shape(&mut b);
+    canonicalize_shape::<Hex>(&mut a);
+    canonicalize_shape::<Hex>(&mut b);
     assert_eq!(a, b);
 }
 
@@ -150,3 +173,977 @@ fn b38y() {
     let expected = expect!["[[[[-2,4],[-3,5],[-2,5],[-3,6]],[[0,0],[-1,1],[-2,2],[-1,2]],[[0,1],[0,2],[0,3],[-1,3],[-1,4]],[[-3,3],[-2,3],[-3,4],[-4,5],[-4,6]]],[[[-4,5],[-3,5],[-4,6],[-3,6]],[[0,1],[0,2],[0,3],[-1,3]],[[0,0],[-1,1],[-2,2],[-1,2],[-2,3]],[[-3,3],[-3,4],[-2,4],[-1,4],[-2,5]]],[[[-4,5],[-3,5],[-4,6],[-3,6]],[[0,0],[0,1],[-1,1],[0,2]],[[-2,2],[-1,2],[0,3],[-2,3],[-1,3]],[[-3,3],[-3,4],[-2,4],[-1,4],[-2,5]]],[[[-4,5],[-3,5],[-4,6],[-3,6]],[[0,0],[0,1],[-1,1],[0,2]],[[-3,3],[-2,3],[-3,4],[-2,4],[-2,5]],[[-2,2],[-1,2],[0,3],[-1,3],[-1,4]]]]"];
     expected.assert_eq(&json);
 }
+
+#[test]
+fn bitboard_shift() {
+    let mask = Bitboard::bit(0) | Bitboard::bit(63) | Bitboard::bit(100);
+    let shifted = &mask << 70;
+    assert_eq!(shifted.ones().collect::<Vec<_>>(), [70, 133, 170]);
+    assert_eq!((&mask << 300).ones().collect::<Vec<_>>(), [300, 363, 400]);
+
+    // the difference keeps a word of zeros, which still compares equal
+    let long = mask.clone() | Bitboard::bit(300);
+    assert_eq!(long - Bitboard::bit(300), mask);
+}
+
+#[test]
+fn large_boards() {
+    let line = |length: i32| rectangle(length, 1);
+
+    let strip = Game::<Square> {
+        board: line(300),
+        pieces: vec![line(100), line(120), line(80)],
+        ..Default::default()
+    };
+    let solver = solve_all(strip, Default::default());
+    assert_eq!(solver.solutions.len(), 6);
+
+    // the rows or the columns
+    let square = Game::<Square> {
+        board: rectangle(17, 17),
+        pieces: vec![line(17); 17],
+        ..Default::default()
+    };
+    let solver = solve_all(
+        square,
+        SolverOptions {
+            distinct_tilings: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(solver.solutions.len(), 2);
+}
+
+#[test]
+fn dlx_matches_solver() {
+    for name in TEST_FILES {
+        let game = test_game(name);
+
+        let solver = solve_all(game.clone(), Default::default());
+
+        let mut dlx = game.dlx_solver();
+        assert_eq!(dlx.solve(), &solver.solutions, "{name}");
+    }
+}
+
+/// Runs a search to the end.
+fn solve_all<L: Lattice>(game: Game<L>, options: SolverOptions) -> Solver<L> {
+    let mut solver = game.solver_with_options(options);
+    solver.by_ref().for_each(drop);
+    solver
+}
+
+fn hexagon_board(radius: i32) -> Vec<Axial> {
+    let range = -radius..=radius;
+    range
+        .clone()
+        .flat_map(|x| range.clone().map(move |y| Axial(x, y)))
+        .filter(|c| (c.0 + c.1).abs() <= radius)
+        .collect()
+}
+
+fn symmetry_class(symmetries: &[Symmetry], solution: &[Vec<Axial>]) -> Vec<Vec<Axial>> {
+    symmetries
+        .iter()
+        .map(|symmetry| {
+            let mut image = solution.to_vec();
+            for piece in &mut image {
+                for coord in piece.iter_mut() {
+                    *coord = symmetry.apply::<Hex>(*coord);
+                }
+                canonicalize_place(piece);
+            }
+            image
+        })
+        .min_by_key(|image| {
+            image
+                .iter()
+                .map(|piece| piece.iter().map(|coord| coord.key()).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        })
+        .unwrap()
+}
+
+#[test]
+fn dedup_symmetries() {
+    let mut game = test_game("b4");
+    game.board = hexagon_board(2);
+
+    let symmetries = board_symmetries::<Hex>(&game.board);
+    assert_eq!(symmetries.len(), 12);
+
+    let solver = solve_all(game.clone(), Default::default());
+    let classes: IndexSet<_> = solver.solutions.iter().map(|s| symmetry_class(&symmetries, s)).collect();
+
+    let deduped = solve_all(
+        game,
+        SolverOptions {
+            dedup_symmetries: true,
+            ..Default::default()
+        },
+    );
+    let deduped_classes: IndexSet<_> = deduped.solutions.iter().map(|s| symmetry_class(&symmetries, s)).collect();
+
+    println!("{} solutions, {} classes", solver.solutions.len(), classes.len());
+    assert_eq!(deduped.solutions.len(), deduped_classes.len());
+    assert_eq!(classes, deduped_classes);
+}
+
+#[test]
+fn identical_pieces() {
+    let mut game = test_game("b4");
+    let mut twin = game.pieces[1].clone();
+    flip::<Hex>(&mut twin);
+    rotate::<Hex>(&mut twin);
+    game.pieces[2] = twin;
+
+    let labelled = solve_all(game.clone(), Default::default());
+    assert_eq!(labelled.identical_pieces, [vec![1, 2]]);
+
+    let mut dlx = game.clone().dlx_solver();
+    assert_eq!(dlx.solve(), &labelled.solutions);
+
+    let distinct = solve_all(
+        game,
+        SolverOptions {
+            distinct_tilings: true,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(distinct.solutions.len(), 4);
+    assert_eq!(labelled.solutions.len(), distinct.solutions.len() * 2);
+}
+
+/// Solves every test file with the default options and with `options`, which have to find the same solutions.
+///
+/// `prepare` changes each game first. The search with `options` has to take fewer steps if `expect_fewer_steps`, and not more otherwise.
+fn assert_same_solutions(options: SolverOptions, prepare: fn(&mut Game), expect_fewer_steps: bool) {
+    for name in TEST_FILES {
+        let mut game = test_game(name);
+        prepare(&mut game);
+
+        let mut solver = game.clone().solver();
+        let steps = solver.by_ref().count();
+
+        let mut changed = game.solver_with_options(options);
+        let changed_steps = changed.by_ref().count();
+
+        println!("{name}: {steps} steps, {changed_steps} steps with {options:?}");
+        assert_eq!(changed.solutions, solver.solutions, "{name}");
+
+        match expect_fewer_steps {
+            true => assert!(changed_steps < steps, "{name}"),
+            false => assert!(changed_steps <= steps, "{name}"),
+        }
+    }
+}
+
+#[test]
+fn prune_regions() {
+    let options = SolverOptions {
+        prune_regions: true,
+        ..Default::default()
+    };
+    assert_same_solutions(options, |_| {}, true);
+
+    let solver = solve_all(test_game("b4"), options);
+    assert!(solver.stats.pruned_branches > 0);
+}
+
+#[test]
+fn branching_cells() {
+    let options = SolverOptions {
+        branching: Branching::Cells,
+        ..Default::default()
+    };
+    assert_same_solutions(options, |_| {}, true);
+}
+
+#[test]
+fn reorder_pieces() {
+    let options = SolverOptions {
+        reorder_pieces: true,
+        ..Default::default()
+    };
+    assert_same_solutions(options, |game| game.pieces.reverse(), false);
+}
+
+#[test]
+fn placements_fit() {
+    let game = test_game("b4");
+    let placements = placements(&game);
+
+    for placement in &placements {
+        let mut expected = game.pieces_permutations()[placement.piece][placement.permutation].clone();
+        translate(&mut expected, placement.translation);
+        assert_eq!(placement.cells, expected);
+        assert!(placement.cells.iter().all(|cell| game.board.contains(cell)));
+    }
+
+    let counts: Vec<usize> = (0..game.pieces.len())
+        .map(|piece| placements.iter().filter(|p| p.piece == piece).count())
+        .collect();
+    expect!["[64, 44, 64, 50]"].assert_eq(&format!("{counts:?}"));
+}
+
+#[test]
+fn solve_parallel() {
+    for name in TEST_FILES {
+        let game = test_game(name);
+
+        let solver = solve_all(game.clone(), Default::default());
+
+        let mut parallel = game.clone().solver();
+        parallel.solve_parallel(4);
+        assert!(parallel.solutions.iter().eq(&solver.solutions), "{name}");
+
+        let mut resumed = game.solver();
+        resumed.by_ref().take(100).for_each(drop);
+        resumed.solve_parallel(4);
+        assert!(resumed.solutions.iter().eq(&solver.solutions), "{name}");
+    }
+}
+
+#[test]
+fn count_only() {
+    let mut game = test_game("b4");
+    game.board = hexagon_board(2);
+
+    let solver = solve_all(game.clone(), Default::default());
+
+    let mut counter = game.solver_with_options(SolverOptions {
+        count_only: true,
+        track_coverage: true,
+        ..Default::default()
+    });
+    counter.solve_parallel(2);
+
+    assert!(counter.solutions.is_empty());
+    assert_eq!(counter.stats.solutions, solver.solutions.len());
+
+    for (piece, coverage) in counter.coverage.iter().enumerate() {
+        for (cell, &count) in coverage.iter().enumerate() {
+            let expected = solver
+                .solutions
+                .iter()
+                .filter(|solution| solution[piece].contains(&counter.game.board[cell]))
+                .count();
+            assert_eq!(count, expected);
+        }
+    }
+}
+
+#[test]
+fn solve_up_to() {
+    let game = test_game("b38y");
+
+    let solver = solve_all(game.clone(), Default::default());
+    let all: Vec<_> = solver.solutions.iter().cloned().collect();
+
+    let mut solver = game.clone().solver();
+    assert_eq!(solver.next_solution().as_ref(), all.first());
+
+    let result = solver.solve_up_to(2);
+    assert_eq!(result.solutions, all[1..3]);
+    assert!(!result.exhausted);
+
+    let result = solver.solve_up_to(usize::MAX);
+    assert_eq!(result.solutions, all[3..]);
+    assert!(result.exhausted && solver.is_finished());
+    assert_eq!(solver.next_solution(), None);
+
+    // a tiling labelled in several ways is still returned one solution at a time
+    let mut twins = test_game("b4");
+    twins.pieces[2] = twins.pieces[1].clone();
+    flip::<Hex>(&mut twins.pieces[2]);
+    rotate::<Hex>(&mut twins.pieces[2]);
+
+    let solver = solve_all(twins.clone(), Default::default());
+    let all: Vec<_> = solver.solutions.iter().cloned().collect();
+
+    let mut solver = twins.solver();
+    let returned: Vec<_> = std::iter::from_fn(|| solver.next_solution()).collect();
+    assert_eq!(returned, all);
+    assert_eq!(all.len(), 8);
+
+    let mut counter = game.clone().solver_with_options(SolverOptions {
+        count_only: true,
+        ..Default::default()
+    });
+    assert!(counter.is_solvable());
+
+    let mut unsolvable = game;
+    unsolvable.board.pop();
+    assert!(!unsolvable.solver().is_solvable());
+}
+
+#[test]
+fn budget() {
+    let game = test_game("b38y");
+
+    let solver = solve_all(game.clone(), Default::default());
+
+    let cancel = CancelToken::new();
+    cancel.cancel();
+
+    let mut budgeted = game.solver();
+    let cancelled = budgeted.run(&Budget {
+        cancel: Some(cancel),
+        ..Default::default()
+    });
+    assert_eq!((cancelled.outcome, cancelled.steps), (RunOutcome::Cancelled, 0));
+
+    let timed_out = budgeted.run(&Budget {
+        time_limit: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    assert_eq!(timed_out.outcome, RunOutcome::TimeLimit);
+
+    let limited = budgeted.run(&Budget {
+        max_steps: Some(10),
+        ..Default::default()
+    });
+    assert_eq!((limited.outcome, limited.steps), (RunOutcome::StepLimit, 10));
+
+    let resumed = budgeted.run(&Budget::default());
+    assert_eq!(resumed.outcome, RunOutcome::Finished);
+    assert_eq!(budgeted.solutions, solver.solutions);
+}
+
+#[test]
+fn checkpoint() {
+    let options = SolverOptions {
+        dedup_symmetries: true,
+        track_coverage: true,
+        ..Default::default()
+    };
+    let mut game = test_game("b4");
+    game.board = hexagon_board(2);
+
+    let solver = solve_all(game.clone(), options);
+
+    let mut interrupted = game.solver_with_options(options);
+    interrupted.by_ref().take(50).for_each(drop);
+
+    let json = serde_json::to_string(&interrupted.checkpoint()).unwrap();
+    let mut resumed = Solver::<Hex>::resume(serde_json::from_str(&json).unwrap()).unwrap();
+    resumed.by_ref().for_each(drop);
+
+    assert_eq!(resumed.solutions, solver.solutions);
+    assert_eq!(resumed.coverage, solver.coverage);
+    assert_eq!(resumed.stats, solver.stats);
+
+    let mut truncated = interrupted.checkpoint();
+    truncated.masks.pop();
+    assert_eq!(Solver::resume(truncated).err(), Some(CheckpointError::WrongLength { field: "masks" }));
+
+    let mut out_of_range = interrupted.checkpoint();
+    out_of_range.placers[1].choices = Choices::Piece {
+        piece: 0,
+        cursor: usize::MAX,
+        optional: false,
+    };
+    assert_eq!(Solver::resume(out_of_range).err(), Some(CheckpointError::InvalidPlacer { depth: 1 }));
+}
+
+#[test]
+fn search_events() {
+    let game = test_game("b38y");
+    let pieces = game.pieces.len();
+    let mut solver = game.solver();
+    let mut stack = Vec::new();
+    let mut found = Vec::new();
+
+    while let Some(event) = solver.next() {
+        match event {
+            SearchEvent::Placed { piece, depth, .. } => {
+                assert_eq!(depth, stack.len());
+                assert!(!stack.contains(&piece));
+                stack.push(piece);
+            }
+            SearchEvent::Backtracked { depth } => {
+                assert!(depth < stack.len());
+                stack.truncate(depth);
+            }
+            SearchEvent::SolutionFound { index } => {
+                assert_eq!(stack.len(), pieces);
+                found.push(index);
+            }
+            SearchEvent::Exhausted { piece } => assert!(piece.is_some_and(|piece| !stack.contains(&piece))),
+            SearchEvent::LeftEmpty { .. } | SearchEvent::Skipped { .. } => unreachable!("every piece is placed and covers the board"),
+            SearchEvent::Finished => assert!(stack.is_empty() && solver.is_finished()),
+        }
+    }
+
+    assert_eq!(found, (0..solver.solutions.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn solver_stats() {
+    let mut solver = test_game("b38y").solver();
+    let placed = solver.by_ref().filter(|event| matches!(event, SearchEvent::Placed { .. })).count();
+
+    let stats = &solver.stats;
+    assert_eq!(stats.solutions, 4);
+    assert_eq!(stats.nodes_per_depth.len(), solver.game.pieces.len());
+    assert_eq!(stats.nodes_per_depth.iter().sum::<usize>(), placed);
+    assert_eq!(stats.backtracks_per_piece.iter().sum::<usize>(), placed);
+    assert_eq!(stats.spots_accepted, placed);
+    assert!(stats.spots_tested >= stats.spots_accepted);
+    assert!(stats.peak_solutions_bytes > 0);
+
+    let json = serde_json::to_string(stats).unwrap();
+    assert_eq!(&serde_json::from_str::<SolverStats>(&json).unwrap(), stats);
+
+    let mut parallel = test_game("b38y").solver();
+    parallel.solve_parallel(2);
+    assert_eq!(parallel.stats.nodes_per_depth, stats.nodes_per_depth);
+    assert_eq!(parallel.stats.backtracks_per_piece, stats.backtracks_per_piece);
+    assert_eq!(parallel.stats.peak_solutions_bytes, stats.peak_solutions_bytes);
+}
+
+#[test]
+fn orientations() {
+    let mut game = test_game("b4");
+    game.board = hexagon_board(2);
+    let solver = solve_all(game.clone(), Default::default());
+
+    let mut one_sided = game;
+    one_sided.orientations = serde_json::from_str(r#"["rotate_only", "free", "flip_only", "rotate_only"]"#).unwrap();
+    let permutations = one_sided.pieces_permutations();
+
+    let expected: IndexSet<Solution> = solver
+        .solutions
+        .iter()
+        .filter(|solution| {
+            solution.iter().zip(&permutations).all(|(cells, permutations)| {
+                let mut shape = cells.clone();
+                canonicalize_shape::<Hex>(&mut shape);
+                permutations.contains(&shape)
+            })
+        })
+        .cloned()
+        .collect();
+
+    let restricted = solve_all(one_sided.clone(), Default::default());
+    assert_eq!(restricted.solutions, expected);
+    assert!(!expected.is_empty() && expected.len() < solver.solutions.len());
+
+    one_sided.orientations = vec![Orientation::RotateOnly; one_sided.pieces.len()];
+    let solver = one_sided.solver_with_options(SolverOptions {
+        dedup_symmetries: true,
+        ..Default::default()
+    });
+    assert!(solver.symmetries.iter().all(|symmetry| !symmetry.transform.flip));
+    assert_eq!(solver.symmetries.len(), 5);
+}
+
+#[test]
+fn piece_pool() {
+    let mut game = test_game("b38y");
+    let board_area = game.board.len();
+    let pieces = game.pieces.clone();
+    game.pieces.extend(pieces.iter().map(|piece| {
+        let mut flipped = piece.clone();
+        flip::<Hex>(&mut flipped);
+        flipped
+    }));
+    game.pieces.push(vec![Axial(0, 0), Axial(1, 0)]);
+
+    for piece_count in [PieceCount::Any, PieceCount::Exactly(4), PieceCount::Exactly(5)] {
+        game.piece_count = piece_count;
+
+        let mut dlx = game.clone().dlx_solver();
+        let expected = dlx.solve();
+        assert!(!expected.is_empty());
+
+        for branching in [Branching::Pieces, Branching::Cells] {
+            let solver = solve_all(
+                game.clone(),
+                SolverOptions {
+                    branching,
+                    prune_regions: true,
+                    ..Default::default()
+                },
+            );
+
+            assert_eq!(&solver.solutions, expected, "{piece_count:?} {branching:?}");
+
+            for solution in &solver.solutions {
+                let used: Vec<usize> = used_pieces(solution).collect();
+                let area: usize = used.iter().map(|&piece| game.pieces[piece].len()).sum();
+                assert_eq!(area, board_area);
+
+                if let PieceCount::Exactly(count) = piece_count {
+                    assert_eq!(used.len(), count);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn piece_counts() {
+    let mut game = test_game("b4");
+    game.pieces.remove(2);
+    game.counts = vec![1, 2, 1];
+
+    let solver = solve_all(game.clone(), Default::default());
+
+    let copies: Vec<_> = solver.copies.iter().map(|copy| (copy.piece, copy.copy)).collect();
+    assert_eq!(copies, [(0, 0), (1, 0), (1, 1), (2, 0)]);
+    assert_eq!(solver.solutions.len(), 4);
+
+    let mut dlx = game.dlx_solver();
+    assert_eq!(dlx.solve(), &solver.solutions);
+
+    let monominoes = Game::<Hex> {
+        board: hexagon_board(1),
+        pieces: vec![vec![Axial(0, 0)]],
+        counts: vec![7],
+        ..Default::default()
+    };
+    let solver = solve_all(monominoes, Default::default());
+    assert_eq!(solver.solutions.len(), 1);
+
+    // copies are never reordered, so many of them stay cheap
+    let monominoes = Game::<Hex> {
+        board: (0..12).map(|x| Axial(x, 0)).collect(),
+        pieces: vec![vec![Axial(0, 0)]],
+        counts: vec![12],
+        ..Default::default()
+    };
+    let solver = solve_all(monominoes, Default::default());
+    assert_eq!(solver.solutions.len(), 1);
+
+    // only the choice of cells per piece matters, 7 choose 3
+    let monominoes = Game::<Hex> {
+        board: hexagon_board(1),
+        pieces: vec![vec![Axial(0, 0)], vec![Axial(0, 0)]],
+        counts: vec![3, 4],
+        ..Default::default()
+    };
+    let solver = solve_all(monominoes, Default::default());
+    assert_eq!(solver.solutions.len(), 35);
+}
+
+#[test]
+fn max_coverage() {
+    let mut game = test_game("b4");
+    let best = game.clone().coverage_solver().solve().clone();
+    assert_eq!(best.score, game.board.len() as u64);
+    assert!(best.uncovered.is_empty());
+
+    game.board.retain(|&cell| cell != Axial(2, 2));
+    let best = game.clone().coverage_solver().solve().clone();
+    let covered: usize = best.pieces.iter().map(Vec::len).sum();
+    assert_eq!(best.score, covered as u64);
+    assert_eq!(best.uncovered.len() + covered, game.board.len());
+    assert!(best
+        .pieces
+        .iter()
+        .flatten()
+        .all(|cell| game.board.contains(cell) && !best.uncovered.contains(cell)));
+    // all pieces would need 19 cells, so the best is the three largest
+    assert_eq!(best.score, 15);
+
+    let bonus = Game::<Hex> {
+        board: vec![Axial(0, 0), Axial(1, 0), Axial(2, 0)],
+        pieces: vec![vec![Axial(0, 0)]],
+        weights: vec![1, 5, 1],
+        ..Default::default()
+    };
+    let best = bonus.coverage_solver().solve().clone();
+    assert_eq!(best.pieces, [vec![Axial(1, 0)]]);
+    assert_eq!((best.score, best.uncovered), (5, vec![Axial(0, 0), Axial(2, 0)]));
+}
+
+#[test]
+fn fixed_pieces() {
+    let game = test_game("b38y");
+    let solver = solve_all(game.clone(), Default::default());
+
+    let first = solver.solutions[0].clone();
+    let mut fixed = game.clone();
+    fixed.fixed = vec![FixedPlacement {
+        piece: 2,
+        cells: first[2].clone(),
+    }];
+
+    let expected: IndexSet<Solution> = solver.solutions.iter().filter(|solution| solution[2] == first[2]).cloned().collect();
+
+    for branching in [Branching::Pieces, Branching::Cells] {
+        let completions = solve_all(
+            fixed.clone(),
+            SolverOptions {
+                branching,
+                ..Default::default()
+            },
+        );
+        assert_eq!(completions.solutions, expected);
+    }
+
+    let mut dlx = fixed.clone().dlx_solver();
+    assert_eq!(dlx.solve(), &expected);
+
+    let best = fixed.clone().coverage_solver().solve().clone();
+    assert_eq!(best.pieces[2], first[2]);
+    assert_eq!(best.score, game.board.len() as u64);
+
+    // a fixed piece stays even where no solution has it
+    let stuck = placements(&game)
+        .into_iter()
+        .find(|placement| placement.piece == 2 && solver.solutions.iter().all(|solution| solution[2] != placement.cells))
+        .unwrap();
+    let mut blocked = game.clone();
+    blocked.fixed = vec![FixedPlacement {
+        piece: 2,
+        cells: stuck.cells.clone(),
+    }];
+    let best = blocked.coverage_solver().solve().clone();
+    assert_eq!(best.pieces[2], stuck.cells);
+    assert!(best.score < game.board.len() as u64);
+
+    let mut invalid = fixed.clone();
+    invalid.fixed.push(invalid.fixed[0].clone());
+    assert_eq!(invalid.validate(), Err(GameError::TooManyFixed { piece: 2 }));
+
+    let overlapping = placements(&game)
+        .into_iter()
+        .find(|placement| placement.piece == 0 && placement.cells.iter().any(|cell| first[2].contains(cell)))
+        .unwrap();
+    invalid.fixed[1] = FixedPlacement {
+        piece: 0,
+        cells: overlapping.cells,
+    };
+    assert!(matches!(invalid.validate(), Err(GameError::Overlap { .. })));
+
+    invalid.fixed[1].cells.pop();
+    assert_eq!(invalid.validate(), Err(GameError::WrongShape { piece: 0 }));
+    assert!(Solver::try_new(invalid.clone()).is_err());
+    assert!(DlxSolver::try_new(invalid.clone()).is_err());
+    assert!(CoverageSolver::try_new(invalid).is_err());
+
+    let mut off_board = fixed;
+    translate(&mut off_board.fixed[0].cells, Axial(100, 0));
+    assert!(matches!(off_board.validate(), Err(GameError::OffBoard { piece: 2, .. })));
+}
+
+fn rectangle(width: i32, height: i32) -> Vec<Axial> {
+    (0..height).flat_map(|y| (0..width).map(move |x| Axial(x, y))).collect()
+}
+
+#[test]
+fn square_lattice() {
+    let pentominoes: Vec<Vec<Axial>> = serde_json::from_str(
+        "[[[1,0],[2,0],[0,1],[1,1],[1,2]], [[0,0],[0,1],[0,2],[0,3],[0,4]], [[0,0],[0,1],[0,2],[0,3],[1,3]],
+          [[1,0],[1,1],[0,2],[1,2],[0,3]], [[0,0],[1,0],[0,1],[1,1],[0,2]], [[0,0],[1,0],[2,0],[1,1],[1,2]],
+          [[0,0],[2,0],[0,1],[1,1],[2,1]], [[0,0],[0,1],[0,2],[1,2],[2,2]], [[0,0],[0,1],[1,1],[1,2],[2,2]],
+          [[1,0],[0,1],[1,1],[2,1],[1,2]], [[1,0],[0,1],[1,1],[1,2],[1,3]], [[0,0],[1,0],[1,1],[1,2],[2,2]]]",
+    )
+    .unwrap();
+
+    let counts: Vec<usize> = pentominoes
+        .iter()
+        .map(|piece| piece_permutations::<Square>(piece.clone(), &Orientation::Free).len())
+        .collect();
+    assert_eq!(counts, [8, 2, 8, 8, 8, 4, 4, 4, 4, 1, 8, 4]);
+    assert_eq!(board_symmetries::<Square>(&rectangle(3, 20)).len(), 4);
+    assert_eq!(board_symmetries::<Square>(&rectangle(4, 4)).len(), 8);
+
+    // L, N, P and U tile a 5x4 rectangle
+    let game = Game::<Square> {
+        board: rectangle(5, 4),
+        pieces: vec![pentominoes[2].clone(), pentominoes[3].clone(), pentominoes[4].clone(), pentominoes[6].clone()],
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&game).unwrap();
+    let game: Game<Square> = serde_json::from_str(&json).unwrap();
+
+    let solver = solve_all(game.clone(), Default::default());
+    let mut dlx = game.clone().dlx_solver();
+    assert_eq!(dlx.solve(), &solver.solutions);
+
+    let deduped = solve_all(
+        game,
+        SolverOptions {
+            dedup_symmetries: true,
+            prune_regions: true,
+            ..Default::default()
+        },
+    );
+    expect![[r#"
+        (
+            8,
+            2,
+        )
+    "#]]
+    .assert_debug_eq(&(solver.solutions.len(), deduped.solutions.len()));
+}
+
+/// Grows every shape of `size` connected cells, as fixed polyforms that are distinct under translation.
+fn polyforms<L: Lattice>(size: usize) -> Vec<Vec<Axial>> {
+    let mut shapes = vec![vec![Axial::ZERO]];
+
+    for _ in 1..size {
+        let mut grown = IndexSet::new();
+
+        for shape in &shapes {
+            for neighbour in shape.iter().flat_map(|&cell| L::neighbours(cell)) {
+                if !shape.contains(&neighbour) {
+                    let mut shape = shape.clone();
+                    shape.push(neighbour);
+                    canonicalize_shape::<L>(&mut shape);
+                    grown.insert(shape);
+                }
+            }
+        }
+
+        shapes = grown.into_iter().collect();
+    }
+
+    shapes
+}
+
+fn count_polyforms<L: Lattice>(size: usize) -> (usize, usize) {
+    let fixed = polyforms::<L>(size);
+    let free: HashSet<_> = fixed
+        .iter()
+        .map(|shape| shape_key(&piece_permutations::<L>(shape.clone(), &Orientation::Free)))
+        .collect();
+
+    (fixed.len(), free.len())
+}
+
+#[test]
+fn lattice_polyforms() {
+    assert_eq!(count_polyforms::<Hex>(4), (44, 7));
+    assert_eq!(count_polyforms::<Square>(5), (63, 12));
+    assert_eq!(count_polyforms::<Triangle>(6), (94, 12));
+
+    let triangle = TriangleCell {
+        corner: Axial(-3, 2),
+        up: false,
+    };
+    assert_eq!(TriangleCell::from(Axial::from(triangle)), triangle);
+    assert_eq!(Triangle::rotate_many(Axial::from(triangle), 6), Axial::from(triangle));
+    assert_eq!(Triangle::flip(Triangle::flip(Axial::from(triangle))), Axial::from(triangle));
+}
+
+#[test]
+fn triangle_lattice() {
+    let game = lattice_game::<Triangle>("t24");
+    let content = std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("data/t24.json")).unwrap();
+    assert!(serde_json::from_str::<Game<Hex>>(&content).is_err());
+    assert!(serde_json::to_string(&game).unwrap().starts_with(r#"{"board":"#));
+    assert!(serde_json::to_string(&game).unwrap().ends_with(r#","lattice":"triangle"}"#));
+    assert_eq!(board_symmetries::<Triangle>(&game.board).len(), 12);
+
+    let solver = solve_all(game.clone(), Default::default());
+    let mut dlx = game.clone().dlx_solver();
+    assert_eq!(dlx.solve(), &solver.solutions);
+
+    let deduped = solve_all(
+        game,
+        SolverOptions {
+            dedup_symmetries: true,
+            prune_regions: true,
+            ..Default::default()
+        },
+    );
+    expect![[r#"
+        (
+            24,
+            2,
+        )
+    "#]]
+    .assert_debug_eq(&(solver.solutions.len(), deduped.solutions.len()));
+}
+
+#[test]
+fn layered_boards() {
+    let soma: Vec<Vec<Voxel>> = serde_json::from_str(
+        "[[[0,0,0],[1,0,0],[0,1,0]], [[0,0,0],[1,0,0],[2,0,0],[0,1,0]], [[0,0,0],[1,0,0],[2,0,0],[1,1,0]],
+          [[0,0,0],[1,0,0],[1,1,0],[2,1,0]], [[0,0,0],[1,0,0],[0,1,0],[1,0,1]], [[0,0,0],[1,0,0],[0,1,0],[0,1,1]],
+          [[0,0,0],[1,0,0],[0,1,0],[0,0,1]]]",
+    )
+    .unwrap();
+    let counts: Vec<usize> = soma.iter().map(|piece| voxel_orientations::<Square>(piece).len()).collect();
+    assert_eq!(counts, [12, 24, 12, 12, 12, 12, 8]);
+
+    let stack = [Voxel::from((0, 0, 0)), Voxel::from((0, 0, 1))];
+    let domino = [Voxel::from((0, 0, 0)), Voxel::from((1, 0, 0))];
+    assert_eq!(voxel_orientations::<Hex>(&stack).len(), 1);
+    assert_eq!(voxel_orientations::<Hex>(&domino).len(), 3);
+    assert_eq!(voxel_orientations::<Square>(&stack).len(), 3);
+
+    let cube = LayeredGame::<Square> {
+        footprint: rectangle(3, 3),
+        layers: 3,
+        pieces: soma,
+        ..Default::default()
+    };
+    let mut solver = cube.solver_with_options(SolverOptions {
+        branching: Branching::Cells,
+        ..Default::default()
+    });
+    let solutions = solver.solve();
+    // 240 ways to build the Soma cube, in each of its 24 rotations and their mirror images
+    assert_eq!(solutions.len(), 240 * 48);
+
+    let board: HashSet<Voxel> = solver.game.board().into_iter().collect();
+    let covered: HashSet<Voxel> = solutions[0].iter().flatten().copied().collect();
+    assert_eq!(covered, board);
+
+    // the checkpoint keeps the orientations in space
+    let brick = LayeredGame::<Square> {
+        footprint: rectangle(3, 2),
+        layers: 2,
+        pieces: vec![solver.game.pieces[0].clone(); 4],
+        ..Default::default()
+    };
+    let solutions = brick.clone().solver().solve();
+    let mut interrupted = brick.clone().solver();
+    interrupted.solver.by_ref().take(20).for_each(drop);
+    let json = serde_json::to_string(&interrupted.solver.checkpoint()).unwrap();
+    let mut resumed = LayeredSolver::resume(brick, serde_json::from_str(&json).unwrap()).unwrap();
+    assert!(!solutions.is_empty());
+    assert_eq!(resumed.solve(), solutions);
+
+    // two stacked hexagons can't fill a board of three layers
+    let tower = LayeredGame::<Hex> {
+        footprint: vec![Axial(0, 0)],
+        layers: 3,
+        pieces: vec![stack.to_vec()],
+        ..Default::default()
+    };
+    assert!(tower.solver().solve().is_empty());
+
+    let json = r#"{"footprint": [[0,0], [1,0]], "layers": 2, "pieces": [[[0,0,0], [0,0,1]], [[0,0,0], [0,0,1]]]}"#;
+    let game: LayeredGame<Hex> = serde_json::from_str(json).unwrap();
+    let solutions = game.solver().solve();
+    // once for each way to swap the identical stacks
+    assert_eq!(solutions.len(), 2);
+    assert_eq!(serde_json::to_string(&solutions[0]).unwrap(), "[[[0,0,0],[0,0,1]],[[1,0,0],[1,0,1]]]");
+}
+
+#[test]
+fn generator() {
+    let options = GeneratorOptions {
+        pieces: PieceSource::Pool {
+            pieces: test_game("b38y").pieces,
+            count: 3,
+        },
+        seed: 7,
+        max_attempts: 1,
+        budget: Budget::default(),
+        solver_options: SolverOptions::default(),
+    };
+    let puzzle = generate::<Hex>(&options).unwrap();
+    let area: usize = puzzle.game.pieces.iter().map(Vec::len).sum();
+    assert_eq!(puzzle.game.board.len(), area);
+    assert!(puzzle.counted_all && puzzle.solutions >= 1);
+
+    let solver = solve_all(puzzle.game.clone(), Default::default());
+    assert_eq!(solver.solutions.len(), puzzle.solutions);
+    assert!(solver.solutions.contains(&puzzle.solution));
+
+    // the board is connected
+    let layout = BoardLayout::new(&puzzle.game.board);
+    let board = layout.mask(&puzzle.game.board).unwrap();
+    assert_eq!(layout.regions(&layout.neighbours::<Hex>(), board).count(), 1);
+
+    let same = generate::<Hex>(&options).unwrap();
+    assert_eq!((same.game.board, same.game.pieces), (puzzle.game.board, puzzle.game.pieces));
+
+    let random = generate::<Square>(&GeneratorOptions {
+        pieces: PieceSource::Random { count: 4, sizes: 3..=5 },
+        seed: 1,
+        ..options
+    })
+    .unwrap();
+    assert_eq!(random.game.pieces.len(), 4);
+    assert!(random.game.pieces.iter().all(|piece| (3..=5).contains(&piece.len())));
+}
+
+#[test]
+fn designer() {
+    let game = test_game("b38y");
+    let options = DesignOptions {
+        target: 1..=1,
+        pool: test_game("b4").pieces,
+        seed: 3,
+        max_iterations: 200,
+        budget: Budget {
+            max_steps: Some(1_000_000),
+            ..Default::default()
+        },
+    };
+    let design = design(game.clone(), &options);
+    assert!(design.reached_target && design.solutions == 1);
+    assert!(design.counts > 1 && design.steps > 0);
+
+    // the edits turn the original game into the designed one
+    let mut replayed = game;
+    design.edits.iter().for_each(|edit| edit.apply(&mut replayed));
+    assert_eq!((&replayed.board, &replayed.pieces), (&design.game.board, &design.game.pieces));
+
+    let area: usize = design.game.pieces.iter().map(Vec::len).sum();
+    assert_eq!(design.game.board.len(), area);
+
+    let solver = solve_all(
+        design.game,
+        SolverOptions {
+            distinct_tilings: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(solver.solutions.len(), 1);
+}
+
+#[test]
+fn designer_respects_fixed_pieces_copies_and_weights() {
+    let b4 = test_game("b4");
+    let first = solve_all(b4.clone(), Default::default()).solutions[0].clone();
+
+    let mut game = b4.clone();
+    game.pieces.pop();
+    game.counts = vec![1, 2, 1];
+    game.fixed = vec![FixedPlacement {
+        piece: 0,
+        cells: first[0].clone(),
+    }];
+    game.weights = (2..).take(game.board.len()).collect();
+    let weights: HashMap<Axial, u32> = (0..game.board.len()).map(|i| (game.board[i], game.weight(i))).collect();
+
+    let options = DesignOptions {
+        target: 1..=2,
+        pool: test_game("b38y").pieces,
+        seed: 1,
+        max_iterations: 100,
+        budget: Budget {
+            max_steps: Some(100_000),
+            ..Default::default()
+        },
+    };
+    let design = design(game.clone(), &options);
+    assert!(design.edits.iter().any(|edit| matches!(edit, Edit::SwapPiece { piece: 1, .. })));
+    assert!(design.game.validate().is_ok());
+    assert_eq!((&design.game.pieces[0], &design.game.fixed), (&game.pieces[0], &game.fixed));
+
+    let area: usize = (0..design.game.pieces.len())
+        .map(|piece| design.game.pieces[piece].len() * design.game.count(piece))
+        .sum();
+    assert_eq!(design.game.board.len(), area);
+
+    // every cell keeps its weight, added cells weigh one
+    let mut edited = design.game;
+    Edit::AddCell(Axial(-1, 0)).apply(&mut edited);
+    Edit::AddCell(Axial(5, 2)).apply(&mut edited);
+    Edit::RemoveCell(Axial(0, 1)).apply(&mut edited);
+    assert_eq!(edited.weights.len(), edited.board.len());
+    for (i, cell) in edited.board.iter().enumerate() {
+        assert_eq!(edited.weight(i), weights.get(cell).copied().unwrap_or(1));
+    }
+}